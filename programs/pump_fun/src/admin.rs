@@ -0,0 +1,573 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use crate::errors::ErrorCode;
+use crate::state::{
+    AccessControl, AllowedQuoteMint, ConfigChange, PendingChange, Role, RoleAssignment, SameSlotSellPolicy,
+    MAX_PENDING_CHANGES, MAX_ROLE_MEMBERS,
+};
+use crate::{
+    ProgramConfig, ConfigUpdateEvent, AdminTransferProposedEvent, AdminTransferAcceptedEvent,
+    AdminTransferCancelledEvent,
+    ConfigChangeQueuedEvent, ConfigChangeExecutedEvent, ConfigChangeCancelledEvent,
+    RoleGrantedEvent, RoleRevokedEvent, CircuitBreakerUpdatedEvent, SandwichProtectionUpdatedEvent,
+    QuoteMintAllowlistEvent, ADMIN_TRANSFER_WINDOW,
+};
+
+// Admin actions shared by the single-key admin instructions and by multisig
+// proposals. Callers are responsible for checking who is allowed to run them.
+
+/// Checks that `authority` holds `role`. The super-admin (`program_config.admin`)
+/// holds every role; everyone else needs a grant in the roles account.
+pub fn require_role(
+    program_config: &ProgramConfig,
+    access_control: Option<&AccessControl>,
+    authority: &Pubkey,
+    role: Role,
+) -> Result<()> {
+    let allowed = *authority == program_config.admin
+        || access_control.is_some_and(|roles| roles.has_role(authority, role));
+    require!(allowed, ErrorCode::Unauthorized);
+    Ok(())
+}
+
+pub fn grant_role(access_control: &mut AccessControl, member: Pubkey, role: Role, now: i64) -> Result<()> {
+    match access_control.members.iter_mut().find(|assignment| assignment.member == member) {
+        Some(assignment) => assignment.roles |= role.mask(),
+        None => {
+            require!(
+                access_control.members.len() < MAX_ROLE_MEMBERS,
+                ErrorCode::TooManyRoleMembers
+            );
+            access_control.members.push(RoleAssignment { member, roles: role.mask() });
+        }
+    }
+
+    emit!(RoleGrantedEvent {
+        member,
+        role,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Revokes a granted role. The admin is the only super-admin and holds every
+/// role implicitly, so it can't be stripped of one here; that takes an admin
+/// transfer.
+pub fn revoke_role(
+    program_config: &ProgramConfig,
+    access_control: &mut AccessControl,
+    member: Pubkey,
+    role: Role,
+    now: i64,
+) -> Result<()> {
+    require_keys_neq!(member, program_config.admin, ErrorCode::CannotRevokeAdmin);
+    require!(access_control.has_role(&member, role), ErrorCode::RoleNotGranted);
+
+    for assignment in access_control.members.iter_mut() {
+        if assignment.member == member {
+            assignment.roles &= !role.mask();
+        }
+    }
+    access_control.members.retain(|assignment| assignment.roles != 0);
+
+    emit!(RoleRevokedEvent {
+        member,
+        role,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn update_program_config(
+    program_config: &mut ProgramConfig,
+    new_fee_collector: Option<Pubkey>,
+    now: i64,
+) -> Result<()> {
+    if let Some(new_fee_collector) = new_fee_collector {
+        queue_change(program_config, ConfigChange::FeeCollector { new_fee_collector }, now)?;
+    }
+    Ok(())
+}
+
+pub fn set_trading_paused(program_config: &mut ProgramConfig, paused: bool) -> Result<()> {
+    program_config.trading_paused = paused;
+    Ok(())
+}
+
+pub fn update_circuit_breaker(
+    program_config: &mut ProgramConfig,
+    threshold_bps: u16,
+    window_slots: u64,
+) -> Result<()> {
+    require!(
+        threshold_bps == 0 || window_slots > 0,
+        ErrorCode::InvalidCircuitBreaker
+    );
+
+    program_config.circuit_breaker_bps = threshold_bps;
+    program_config.circuit_breaker_window_slots = window_slots;
+
+    emit!(CircuitBreakerUpdatedEvent {
+        threshold_bps,
+        window_slots,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Applies new sandwich protection settings, except that anything raising
+/// the surcharge same-slot sellers pay is queued behind the timelock like a
+/// fee increase.
+pub fn update_sandwich_protection(
+    program_config: &mut ProgramConfig,
+    policy: SameSlotSellPolicy,
+    surcharge_bps: u16,
+    max_buy_per_slot: u64,
+    now: i64,
+) -> Result<()> {
+    let change = ConfigChange::SandwichProtection { policy, surcharge_bps, max_buy_per_slot };
+    change.validate()?;
+
+    let current_surcharge_bps = effective_surcharge_bps(
+        program_config.same_slot_sell_policy,
+        program_config.same_slot_surcharge_bps,
+    );
+    if effective_surcharge_bps(policy, surcharge_bps) > current_surcharge_bps {
+        return queue_change(program_config, change, now);
+    }
+    set_sandwich_protection(program_config, policy, surcharge_bps, max_buy_per_slot, now)
+}
+
+/// Surcharge a same-slot sell actually pays under `policy`.
+fn effective_surcharge_bps(policy: SameSlotSellPolicy, surcharge_bps: u16) -> u16 {
+    match policy {
+        SameSlotSellPolicy::Surcharge => surcharge_bps,
+        SameSlotSellPolicy::Allow | SameSlotSellPolicy::Reject => 0,
+    }
+}
+
+fn set_sandwich_protection(
+    program_config: &mut ProgramConfig,
+    policy: SameSlotSellPolicy,
+    surcharge_bps: u16,
+    max_buy_per_slot: u64,
+    now: i64,
+) -> Result<()> {
+    program_config.same_slot_sell_policy = policy;
+    program_config.same_slot_surcharge_bps = surcharge_bps;
+    program_config.max_buy_per_slot = max_buy_per_slot;
+
+    emit!(SandwichProtectionUpdatedEvent {
+        policy,
+        surcharge_bps,
+        max_buy_per_slot,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Builds the allowlist entry for `quote_mint`. Curve prices are in
+/// lamport-sized units, so only mints with SOL's 9 decimals are accepted.
+pub fn allow_quote_mint(quote_mint: &Account<Mint>, bump: u8) -> Result<AllowedQuoteMint> {
+    require!(quote_mint.decimals == 9, ErrorCode::UnsupportedQuoteDecimals);
+
+    emit!(QuoteMintAllowlistEvent {
+        quote_mint: quote_mint.key(),
+        allowed: true,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(AllowedQuoteMint {
+        mint: quote_mint.key(),
+        bump,
+    })
+}
+
+pub fn disallow_quote_mint(quote_mint: Pubkey) -> Result<()> {
+    emit!(QuoteMintAllowlistEvent {
+        quote_mint,
+        allowed: false,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Withdraws collected SOL fees from the fee vault, never touching its rent
+/// reserve. The vault is program-owned, so callers must check the withdrawer's
+/// role first: this debits it without any signature.
+pub fn withdraw_fees<'info>(
+    fee_vault: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let rent_reserve = Rent::get()?.minimum_balance(fee_vault.data_len());
+    require!(
+        fee_vault.lamports().saturating_sub(rent_reserve) >= amount,
+        ErrorCode::InsufficientFees
+    );
+    fee_vault.sub_lamports(amount)?;
+    recipient.add_lamports(amount)?;
+    Ok(())
+}
+
+/// Starts an admin transfer. The new key can accept once the timelock delay
+/// has passed, and has `ADMIN_TRANSFER_WINDOW` after that to do so.
+pub fn propose_admin(program_config: &mut ProgramConfig, new_admin: Pubkey, now: i64) -> Result<()> {
+    require!(
+        new_admin != Pubkey::default() && new_admin != program_config.admin,
+        ErrorCode::InvalidAdminAddress
+    );
+
+    let eta = now
+        .checked_add(program_config.timelock_delay)
+        .ok_or(ErrorCode::Overflow)?;
+    let expires_at = eta
+        .checked_add(ADMIN_TRANSFER_WINDOW)
+        .ok_or(ErrorCode::Overflow)?;
+    program_config.pending_admin = Some(new_admin);
+    program_config.pending_admin_eta = eta;
+    program_config.pending_admin_expires_at = expires_at;
+
+    emit!(AdminTransferProposedEvent {
+        admin: program_config.admin,
+        pending_admin: new_admin,
+        eta,
+        expires_at,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Completes a pending admin transfer. `authority` is the key that signed
+/// for it: the pending admin itself, or the multisig PDA via a proposal.
+pub fn accept_admin(program_config: &mut ProgramConfig, authority: Pubkey, now: i64) -> Result<()> {
+    let pending_admin = program_config.pending_admin.ok_or(ErrorCode::NoPendingAdmin)?;
+    require!(authority == pending_admin, ErrorCode::Unauthorized);
+    require!(
+        now >= program_config.pending_admin_eta,
+        ErrorCode::TimelockNotElapsed
+    );
+    require!(
+        now <= program_config.pending_admin_expires_at,
+        ErrorCode::AdminTransferExpired
+    );
+
+    let previous_admin = program_config.admin;
+    program_config.admin = pending_admin;
+    program_config.clear_pending_admin();
+
+    emit!(AdminTransferAcceptedEvent {
+        previous_admin,
+        admin: pending_admin,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_admin_transfer(program_config: &mut ProgramConfig, now: i64) -> Result<()> {
+    let pending_admin = program_config.pending_admin.ok_or(ErrorCode::NoPendingAdmin)?;
+
+    program_config.clear_pending_admin();
+
+    emit!(AdminTransferCancelledEvent {
+        admin: program_config.admin,
+        pending_admin,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Queues a timelocked change. It can be applied by anyone with
+/// `execute_change` once `timelock_delay` has passed.
+pub fn queue_change(program_config: &mut ProgramConfig, change: ConfigChange, now: i64) -> Result<()> {
+    change.validate()?;
+    require!(
+        program_config.pending_changes.len() < MAX_PENDING_CHANGES,
+        ErrorCode::TooManyPendingChanges
+    );
+
+    let eta = now
+        .checked_add(program_config.timelock_delay)
+        .ok_or(ErrorCode::Overflow)?;
+    let id = program_config.next_change_id;
+    program_config.next_change_id = id.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    program_config.pending_changes.push(PendingChange { id, change: change.clone(), eta });
+
+    emit!(ConfigChangeQueuedEvent {
+        id,
+        change,
+        eta,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_change(program_config: &mut ProgramConfig, id: u64, now: i64) -> Result<()> {
+    let pending = program_config.take_pending_change(id)?;
+
+    emit!(ConfigChangeCancelledEvent {
+        id,
+        change: pending.change,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn execute_change(program_config: &mut ProgramConfig, id: u64, now: i64) -> Result<()> {
+    let eta = program_config
+        .pending_changes
+        .iter()
+        .find(|pending| pending.id == id)
+        .map(|pending| pending.eta)
+        .ok_or(ErrorCode::ChangeNotFound)?;
+    require!(now >= eta, ErrorCode::TimelockNotElapsed);
+    let pending = program_config.take_pending_change(id)?;
+
+    match pending.change {
+        ConfigChange::TradingFee { new_fee_bps } => {
+            program_config.trading_fee_bps = new_fee_bps;
+        }
+        ConfigChange::CreationFee { new_fee_lamports } => {
+            program_config.creation_fee_lamports = new_fee_lamports;
+        }
+        ConfigChange::FeeCollector { new_fee_collector } => {
+            program_config.fee_collector = new_fee_collector;
+        }
+        ConfigChange::TimelockDelay { new_delay } => {
+            program_config.timelock_delay = new_delay;
+        }
+        ConfigChange::SandwichProtection { policy, surcharge_bps, max_buy_per_slot } => {
+            set_sandwich_protection(program_config, policy, surcharge_bps, max_buy_per_slot, now)?;
+        }
+    }
+
+    emit!(ConfigChangeExecutedEvent {
+        id,
+        change: pending.change,
+        timestamp: now,
+    });
+    emit!(ConfigUpdateEvent {
+        admin: program_config.admin,
+        fee_collector: program_config.fee_collector,
+        trading_fee_bps: program_config.trading_fee_bps,
+        creation_fee_lamports: program_config.creation_fee_lamports,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::CURRENT_ACCOUNT_VERSION;
+    use crate::CONFIG_RESERVED_BYTES;
+
+    fn config(admin: Pubkey, timelock_delay: i64) -> ProgramConfig {
+        ProgramConfig {
+            version: CURRENT_ACCOUNT_VERSION,
+            fee_collector: Pubkey::new_unique(),
+            admin,
+            trading_paused: false,
+            trading_fee_bps: 100,
+            creation_fee_lamports: 0,
+            bump: 255,
+            pending_admin: None,
+            pending_admin_eta: 0,
+            pending_admin_expires_at: 0,
+            timelock_delay,
+            next_change_id: 0,
+            pending_changes: Vec::new(),
+            circuit_breaker_bps: 0,
+            circuit_breaker_window_slots: 0,
+            same_slot_sell_policy: SameSlotSellPolicy::Allow,
+            same_slot_surcharge_bps: 0,
+            max_buy_per_slot: 0,
+            reserved: [0; CONFIG_RESERVED_BYTES],
+        }
+    }
+
+    fn roles() -> AccessControl {
+        AccessControl { members: Vec::new(), bump: 255 }
+    }
+
+    #[test]
+    fn granted_roles_are_held_separately() {
+        let member = Pubkey::new_unique();
+        let mut roles = roles();
+        grant_role(&mut roles, member, Role::Pauser, 0).unwrap();
+        grant_role(&mut roles, member, Role::Moderator, 0).unwrap();
+
+        assert_eq!(roles.members.len(), 1);
+        assert!(roles.has_role(&member, Role::Pauser));
+        assert!(roles.has_role(&member, Role::Moderator));
+        assert!(!roles.has_role(&member, Role::TreasuryWithdrawer));
+        assert!(!roles.has_role(&Pubkey::new_unique(), Role::Pauser));
+    }
+
+    #[test]
+    fn roles_account_has_a_member_cap() {
+        let mut roles = roles();
+        for _ in 0..MAX_ROLE_MEMBERS {
+            grant_role(&mut roles, Pubkey::new_unique(), Role::Pauser, 0).unwrap();
+        }
+        assert_eq!(
+            grant_role(&mut roles, Pubkey::new_unique(), Role::Pauser, 0).unwrap_err(),
+            ErrorCode::TooManyRoleMembers.into()
+        );
+    }
+
+    #[test]
+    fn revoking_the_last_role_removes_the_member() {
+        let config = config(Pubkey::new_unique(), 0);
+        let member = Pubkey::new_unique();
+        let mut roles = roles();
+        grant_role(&mut roles, member, Role::Pauser, 0).unwrap();
+        grant_role(&mut roles, member, Role::FeeManager, 0).unwrap();
+
+        revoke_role(&config, &mut roles, member, Role::Pauser, 0).unwrap();
+        assert!(!roles.has_role(&member, Role::Pauser));
+        assert!(roles.has_role(&member, Role::FeeManager));
+        assert_eq!(
+            revoke_role(&config, &mut roles, member, Role::Pauser, 0).unwrap_err(),
+            ErrorCode::RoleNotGranted.into()
+        );
+
+        revoke_role(&config, &mut roles, member, Role::FeeManager, 0).unwrap();
+        assert!(roles.members.is_empty());
+    }
+
+    #[test]
+    fn admin_holds_every_role_and_cannot_be_revoked() {
+        let admin = Pubkey::new_unique();
+        let config = config(admin, 0);
+        let mut roles = roles();
+        assert!(require_role(&config, None, &admin, Role::TreasuryWithdrawer).is_ok());
+        assert_eq!(
+            require_role(&config, Some(&roles), &Pubkey::new_unique(), Role::TreasuryWithdrawer).unwrap_err(),
+            ErrorCode::Unauthorized.into()
+        );
+
+        // The admin is the only super-admin; it isn't removed through roles
+        grant_role(&mut roles, admin, Role::Pauser, 0).unwrap();
+        assert_eq!(
+            revoke_role(&config, &mut roles, admin, Role::Pauser, 0).unwrap_err(),
+            ErrorCode::CannotRevokeAdmin.into()
+        );
+        assert!(require_role(&config, Some(&roles), &admin, Role::Pauser).is_ok());
+    }
+
+    #[test]
+    fn queued_change_executes_after_the_delay() {
+        let mut config = config(Pubkey::new_unique(), 100);
+        queue_change(&mut config, ConfigChange::TradingFee { new_fee_bps: 200 }, 1_000).unwrap();
+        assert_eq!(config.pending_changes[0].eta, 1_100);
+
+        assert_eq!(
+            execute_change(&mut config, 0, 1_099).unwrap_err(),
+            ErrorCode::TimelockNotElapsed.into()
+        );
+        assert_eq!(config.trading_fee_bps, 100);
+
+        execute_change(&mut config, 0, 1_100).unwrap();
+        assert_eq!(config.trading_fee_bps, 200);
+        assert!(config.pending_changes.is_empty());
+        assert_eq!(
+            execute_change(&mut config, 0, 1_100).unwrap_err(),
+            ErrorCode::ChangeNotFound.into()
+        );
+    }
+
+    #[test]
+    fn surcharge_increase_waits_for_the_timelock() {
+        let mut config = config(Pubkey::new_unique(), 100);
+        update_sandwich_protection(&mut config, SameSlotSellPolicy::Surcharge, 500, 0, 1_000).unwrap();
+        assert_eq!(config.same_slot_sell_policy, SameSlotSellPolicy::Allow);
+        assert_eq!(config.pending_changes.len(), 1);
+
+        execute_change(&mut config, 0, 1_100).unwrap();
+        assert_eq!(config.same_slot_sell_policy, SameSlotSellPolicy::Surcharge);
+        assert_eq!(config.same_slot_surcharge_bps, 500);
+
+        // Lowering the surcharge or changing the policy applies right away
+        update_sandwich_protection(&mut config, SameSlotSellPolicy::Surcharge, 100, 0, 1_200).unwrap();
+        assert_eq!(config.same_slot_surcharge_bps, 100);
+        update_sandwich_protection(&mut config, SameSlotSellPolicy::Reject, 10_000, 5, 1_200).unwrap();
+        assert_eq!(config.same_slot_sell_policy, SameSlotSellPolicy::Reject);
+        assert_eq!(config.max_buy_per_slot, 5);
+        assert!(config.pending_changes.is_empty());
+
+        // Switching back to the surcharge policy would now charge 100%
+        update_sandwich_protection(&mut config, SameSlotSellPolicy::Surcharge, 10_000, 5, 1_200).unwrap();
+        assert_eq!(config.same_slot_sell_policy, SameSlotSellPolicy::Reject);
+        assert_eq!(config.pending_changes.len(), 1);
+    }
+
+    #[test]
+    fn cancelled_change_never_executes() {
+        let mut config = config(Pubkey::new_unique(), 100);
+        queue_change(&mut config, ConfigChange::TradingFee { new_fee_bps: 200 }, 1_000).unwrap();
+
+        cancel_change(&mut config, 0, 1_050).unwrap();
+        assert_eq!(
+            execute_change(&mut config, 0, 1_100).unwrap_err(),
+            ErrorCode::ChangeNotFound.into()
+        );
+        assert_eq!(
+            cancel_change(&mut config, 0, 1_100).unwrap_err(),
+            ErrorCode::ChangeNotFound.into()
+        );
+        assert_eq!(config.trading_fee_bps, 100);
+    }
+
+    #[test]
+    fn multisig_takes_over_only_after_the_delay() {
+        let admin = Pubkey::new_unique();
+        let multisig = Pubkey::new_unique();
+        let mut config = config(admin, 100);
+        propose_admin(&mut config, multisig, 1_000).unwrap();
+        assert_eq!(config.admin, admin);
+
+        assert_eq!(
+            accept_admin(&mut config, multisig, 1_099).unwrap_err(),
+            ErrorCode::TimelockNotElapsed.into()
+        );
+        assert_eq!(
+            accept_admin(&mut config, Pubkey::new_unique(), 1_100).unwrap_err(),
+            ErrorCode::Unauthorized.into()
+        );
+        assert_eq!(
+            accept_admin(&mut config, multisig, 1_101 + ADMIN_TRANSFER_WINDOW).unwrap_err(),
+            ErrorCode::AdminTransferExpired.into()
+        );
+
+        accept_admin(&mut config, multisig, 1_100).unwrap();
+        assert_eq!(config.admin, multisig);
+        assert_eq!(config.pending_admin, None);
+    }
+
+    #[test]
+    fn cancelled_admin_transfer_cannot_be_accepted() {
+        let admin = Pubkey::new_unique();
+        let multisig = Pubkey::new_unique();
+        let mut config = config(admin, 100);
+        propose_admin(&mut config, multisig, 1_000).unwrap();
+
+        cancel_admin_transfer(&mut config, 1_050).unwrap();
+        assert_eq!(
+            accept_admin(&mut config, multisig, 1_100).unwrap_err(),
+            ErrorCode::NoPendingAdmin.into()
+        );
+        assert_eq!(config.admin, admin);
+    }
+}
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::errors::ErrorCode;
 
+#[allow(dead_code)]
 pub const TOTAL_SUPPLY: u64 = 1_000_000_000_000_000; // 1 billion tokens (with 6 decimals)
 pub const CURVE_SUPPLY: u64 = 800_000_000_000_000;   // 800 million tokens (with 6 decimals)
 pub const TOKENS_PER_STEP: u64 = 10_000_000_000_000; // 10M tokens (with 6 decimals)
@@ -8,69 +9,110 @@ pub const TOKENS_PER_STEP: u64 = 10_000_000_000_000; // 10M tokens (with 6 decim
 pub struct BondingCurve;
 
 impl BondingCurve {
-    pub fn calculate_price(market_cap: u64) -> u64 {
+    pub fn calculate_price(market_cap: u64) -> Result<u64> {
         // y = 0.6015 * e^(0.00003606x)
         
         // Base price: 0.6015 SOL
-        let base_price: u64 = 601_500_000; // 0.6015 SOL in lamports
+        let base_price: u128 = 601_500_000; // 0.6015 SOL in lamports
         
-        let exp_factor = Self::calculate_exp_factor(market_cap);
+        let exp_factor = Self::calculate_exp_factor(market_cap)?;
         
-        base_price
+        let price = base_price
             .checked_mul(exp_factor)
-            .unwrap_or(u64::MAX)
-            .checked_div(1_000_000)
-            .unwrap_or(u64::MAX)
+            .ok_or(ErrorCode::Overflow)?
+            / 1_000_000;
+        u64::try_from(price).map_err(|_| error!(ErrorCode::Overflow))
     }
 
-    fn calculate_exp_factor(market_cap: u64) -> u64 {
-        let x: u64 = market_cap
-            .checked_mul(36060)
-            .unwrap_or(u64::MAX)
-            .checked_div(1_000_000)
-            .unwrap_or(u64::MAX);
+    // Third-order Taylor expansion of the exponent, scaled by 1e6. Done in
+    // u128 so realistic market caps can't overflow the intermediate powers.
+    fn calculate_exp_factor(market_cap: u64) -> Result<u128> {
+        let x = u128::from(market_cap) * 36060 / 1_000_000;
 
         let x_squared = x
             .checked_mul(x)
-            .unwrap_or(u64::MAX)
-            .checked_div(1_000_000)
-            .unwrap_or(u64::MAX);
+            .ok_or(ErrorCode::Overflow)?
+            / 1_000_000;
 
         let x_cubed = x_squared
             .checked_mul(x)
-            .unwrap_or(u64::MAX)
-            .checked_div(1_000_000)
-            .unwrap_or(u64::MAX);
+            .ok_or(ErrorCode::Overflow)?
+            / 1_000_000;
 
-        let base: u64 = 1_000_000;
+        let base: u128 = 1_000_000;
         base
             .checked_add(x)
-            .unwrap_or(u64::MAX)
-            .checked_add(x_squared.checked_div(2).unwrap_or(u64::MAX))
-            .unwrap_or(u64::MAX)
-            .checked_add(x_cubed.checked_div(6).unwrap_or(u64::MAX))
-            .unwrap_or(u64::MAX)
+            .and_then(|sum| sum.checked_add(x_squared / 2))
+            .and_then(|sum| sum.checked_add(x_cubed / 6))
+            .ok_or(error!(ErrorCode::Overflow))
     }
 
     pub fn calculate_tokens_out(sol_amount: u64, current_market_cap: u64) -> Result<u64> {
-        let price = Self::calculate_price(current_market_cap);
-        sol_amount
-            .checked_mul(TOKENS_PER_STEP)
-            .unwrap_or(u64::MAX)
-            .checked_div(price)
-            .ok_or(error!(ErrorCode::CalculationError))
+        let price = Self::calculate_price(current_market_cap)?;
+        let tokens_out = (u128::from(sol_amount) * u128::from(TOKENS_PER_STEP))
+            .checked_div(u128::from(price))
+            .ok_or(ErrorCode::CalculationError)?;
+        u64::try_from(tokens_out).map_err(|_| error!(ErrorCode::Overflow))
     }
 
     pub fn calculate_sol_out(token_amount: u64, current_market_cap: u64) -> Result<u64> {
-        let price = Self::calculate_price(current_market_cap);
-        token_amount
-            .checked_mul(price)
-            .unwrap_or(u64::MAX)
-            .checked_div(TOKENS_PER_STEP)
-            .ok_or(error!(ErrorCode::CalculationError))
+        let price = Self::calculate_price(current_market_cap)?;
+        let sol_out = u128::from(token_amount) * u128::from(price) / u128::from(TOKENS_PER_STEP);
+        u64::try_from(sol_out).map_err(|_| error!(ErrorCode::Overflow))
     }
 
     pub fn should_transition_to_raydium(tokens_sold: u64) -> bool {
         tokens_sold >= CURVE_SUPPLY
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_starts_at_base_price() {
+        assert_eq!(BondingCurve::calculate_price(0).unwrap(), 601_500_000);
+    }
+
+    #[test]
+    fn price_increases_with_market_cap() {
+        let low = BondingCurve::calculate_price(1_000_000_000).unwrap();
+        let high = BondingCurve::calculate_price(17_000_000_000).unwrap();
+        assert!(high > low);
+    }
+
+    #[test]
+    fn price_overflow_is_an_error() {
+        assert_eq!(
+            BondingCurve::calculate_price(u64::MAX).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn large_buy_does_not_saturate() {
+        // 100 SOL used to overflow the u64 intermediate and mint u64::MAX / price
+        let tokens = BondingCurve::calculate_tokens_out(100_000_000_000, 0).unwrap();
+        assert_eq!(
+            u128::from(tokens),
+            100_000_000_000u128 * u128::from(TOKENS_PER_STEP) / 601_500_000
+        );
+    }
+
+    #[test]
+    fn buy_overflow_is_an_error() {
+        assert_eq!(
+            BondingCurve::calculate_tokens_out(u64::MAX, 0).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn sell_overflow_is_an_error() {
+        assert_eq!(
+            BondingCurve::calculate_sol_out(u64::MAX, 100_000_000_000_000).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+    }
+}
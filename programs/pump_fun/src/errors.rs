@@ -26,4 +26,138 @@ pub enum ErrorCode {
     InvalidFeePercentage,
     #[msg("Invalid admin address")]
     InvalidAdminAddress,
+    #[msg("Invalid creation fee")]
+    InvalidCreationFee,
+    #[msg("Display name too long")]
+    DisplayNameTooLong,
+    #[msg("Avatar URI too long")]
+    AvatarUriTooLong,
+    #[msg("Symbol must be 1-10 ASCII letters or digits")]
+    InvalidSymbol,
+    #[msg("Symbol does not match the token metadata")]
+    SymbolMismatch,
+    #[msg("Token has been delisted; only sells are allowed")]
+    TokenDelisted,
+    #[msg("No admin transfer is pending")]
+    NoPendingAdmin,
+    #[msg("Admin transfer has expired")]
+    AdminTransferExpired,
+    #[msg("Multisig needs 1-10 unique signers and a threshold between 1 and the signer count")]
+    InvalidMultisig,
+    #[msg("Signer is not a member of the multisig")]
+    NotMultisigSigner,
+    #[msg("Proposal already approved by this signer")]
+    AlreadyApproved,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Proposal has not reached the approval threshold")]
+    ThresholdNotMet,
+    #[msg("Multisig is no longer the program admin")]
+    MultisigNotAdmin,
+    #[msg("Missing or mismatched account for this proposal action")]
+    InvalidProposalAccounts,
+    #[msg("Invalid fee collector address")]
+    InvalidFeeCollector,
+    #[msg("Timelock delay must be between 0 and 30 days")]
+    InvalidTimelockDelay,
+    #[msg("Too many config changes are already queued")]
+    TooManyPendingChanges,
+    #[msg("No queued config change with this id")]
+    ChangeNotFound,
+    #[msg("Timelock delay has not elapsed yet")]
+    TimelockNotElapsed,
+    #[msg("Roles account is full")]
+    TooManyRoleMembers,
+    #[msg("Member does not hold this role")]
+    RoleNotGranted,
+    #[msg("Market is halted by the circuit breaker")]
+    MarketHalted,
+    #[msg("Circuit breaker needs a window when a threshold is set")]
+    InvalidCircuitBreaker,
+    #[msg("Account is not a known older layout of this account type")]
+    InvalidAccountVersion,
+    #[msg("Account is already on the current layout")]
+    AccountAlreadyMigrated,
+    #[msg("Trade amount must be greater than zero")]
+    ZeroAmount,
+    #[msg("Trade is too small to receive anything")]
+    DustTrade,
+    #[msg("Cannot sell more tokens than the curve has issued")]
+    SellExceedsSupply,
+    #[msg("Mint supply does not match the curve's issued supply")]
+    CurveSupplyMismatch,
+    #[msg("Cannot sell in the same slot as your last buy")]
+    SameSlotSell,
+    #[msg("Buy exceeds the per-slot limit for this wallet")]
+    SlotBuyLimitExceeded,
+    #[msg("Same-slot surcharge must be at most 10000 bps")]
+    InvalidSandwichProtection,
+    #[msg("Invalid launch protection settings")]
+    InvalidLaunchProtection,
+    #[msg("Trading has not opened for this token yet")]
+    TradingNotOpen,
+    #[msg("Buy exceeds the per-wallet cap for the launch window")]
+    LaunchBuyLimitExceeded,
+    #[msg("Presale must be created at launch, before any trade, and last at most a day")]
+    InvalidPresale,
+    #[msg("Presale is not open")]
+    PresaleClosed,
+    #[msg("Wallet is not on the presale allowlist")]
+    NotOnAllowlist,
+    #[msg("Buy exceeds the wallet's presale allocation")]
+    PresaleAllocationExceeded,
+    #[msg("Auction must be created at launch before any trade, last at most an hour and not overlap a presale")]
+    InvalidAuction,
+    #[msg("Auction is not accepting bids")]
+    AuctionClosed,
+    #[msg("Auction is still running")]
+    AuctionNotEnded,
+    #[msg("Auction is already finalized")]
+    AuctionAlreadyFinalized,
+    #[msg("Auction has not been finalized")]
+    AuctionNotFinalized,
+    #[msg("Spot price has not crossed the order's limit")]
+    OrderNotTriggered,
+    #[msg("Order needs a limit price, or for sells a stop below the limit")]
+    InvalidOrderPrices,
+    #[msg("DCA amount must be positive and the interval at least a minute")]
+    InvalidDcaSchedule,
+    #[msg("DCA vault is paused")]
+    DcaPaused,
+    #[msg("DCA period is not due yet")]
+    DcaNotDue,
+    #[msg("DCA vault balance is too low for another period")]
+    DcaInsufficientBalance,
+    #[msg("Spot price is above the DCA vault's limit")]
+    DcaPriceAboveLimit,
+    #[msg("Batch must have between 1 and 5 legs")]
+    InvalidBatch,
+    #[msg("Batch accounts don't match the legs")]
+    InvalidBatchAccounts,
+    #[msg("Trade uses the wrong quote currency for this curve")]
+    QuoteCurrencyMismatch,
+    #[msg("Quote currency can only be set in the launch slot, before any trade")]
+    QuoteMintLocked,
+    #[msg("Sell share must be between 1 and 10000 bps, and 10000 to close the account")]
+    InvalidSellShare,
+    #[msg("Trade deadline has passed")]
+    TradeDeadlineExceeded,
+    #[msg("Trade is larger than the trader's balance")]
+    InsufficientBalance,
+    #[msg("Quote mints must have 9 decimals, like SOL")]
+    UnsupportedQuoteDecimals,
+    #[msg("Proposal has expired")]
+    ProposalExpired,
+    #[msg("A dev-buy needs the creator's token account")]
+    MissingCreatorTokenAccount,
+    #[msg("Sell orders need a token escrow and buy orders don't take one")]
+    InvalidOrderEscrow,
+    #[msg("No multisig update is queued")]
+    NoPendingMultisigUpdate,
+    #[msg("Mint authority must be the curve, or the creator signing the migration to hand it over")]
+    LegacyMintAuthority,
+    #[msg("Fee vault holds less than the withdrawal above its rent reserve")]
+    InsufficientFees,
+    #[msg("The admin holds every role; transfer the admin instead of revoking")]
+    CannotRevokeAdmin,
 } 
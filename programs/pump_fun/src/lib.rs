@@ -1,11 +1,25 @@
 mod state;
 mod errors;
 mod bonding_curve;
+mod trade;
+mod admin;
+mod migration;
 
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{self, Token, TokenAccount, Mint};
-use state::{TokenMetadata, BondingCurveParams};
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use state::{
+    TokenMetadata, BondingCurveParams, CreatorProfile, SymbolRegistry, ModerationStatus, Multisig,
+    Proposal, AdminAction, ConfigChange, AccessControl, Role, PendingChange, CURRENT_ACCOUNT_VERSION,
+    ACCOUNT_RESERVED_BYTES, CURVE_RESERVED_BYTES, SameSlotSellPolicy, UserTradeState, LaunchProtection,
+    Presale, PresaleAllocation, Auction, AuctionBid, LimitOrder, OrderSide, DcaVault,
+    TradeLeg, TradeDeadline, AllowedQuoteMint, FeeVault, MIN_DCA_INTERVAL_SECONDS, MAX_PRESALE_SLOTS, MAX_AUCTION_SECONDS,
+    MAX_SYMBOL_LEN, MAX_PENDING_CHANGES, MAX_DISPLAY_NAME_LEN, MAX_AVATAR_URI_LEN,
+};
 use errors::ErrorCode;
+use trade::{CurveTrade, QuoteAccounts};
 use bonding_curve::BondingCurve;
 
 declare_id!("9e7FCcemFyvPUrXgUfxKCZvNVpLiiYMo34t77Kwa241u");
@@ -14,6 +28,7 @@ declare_id!("9e7FCcemFyvPUrXgUfxKCZvNVpLiiYMo34t77Kwa241u");
 pub mod pump_fun {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_token(
         ctx: Context<CreateTokenContext>,
         name: String,
@@ -21,19 +36,26 @@ pub mod pump_fun {
         description: String,
         image_url: String,
         _initial_supply: u64,
+        initial_buy_sol: Option<u64>,
+        launch_protection: Option<LaunchProtection>,
     ) -> Result<()> {
         // Validate inputs
         require!(name.len() <= 32, ErrorCode::NameTooLong);
-        require!(symbol.len() <= 10, ErrorCode::SymbolTooLong);
+        require!(symbol.len() <= MAX_SYMBOL_LEN, ErrorCode::SymbolTooLong);
+        let launch_protection = launch_protection.unwrap_or_default();
+        launch_protection.validate()?;
+        let clock = Clock::get()?;
         
         // Create metadata for the token
         let token_metadata = TokenMetadata {
+            version: CURRENT_ACCOUNT_VERSION,
             name,
             symbol,
             description,
             image_url,
             creator: ctx.accounts.authority.key(),
-            creation_time: Clock::get()?.unix_timestamp,
+            creation_time: clock.unix_timestamp,
+            reserved: [0; ACCOUNT_RESERVED_BYTES],
         };
 
         // Store metadata on-chain
@@ -41,15 +63,85 @@ pub mod pump_fun {
 
         // Initialize bonding curve parameters
         let curve_params = BondingCurveParams {
+            version: CURRENT_ACCOUNT_VERSION,
             initial_price: 1_000_000,    // 0.001 SOL
             slope: 100,                  // Price increase rate
             liquidity_target: 17_000_000_000, // $17k in lamports
             current_supply: 0,           // Start with 0 tokens sold
             total_liquidity: 0,          // Start with 0 SOL in liquidity
             bump: ctx.bumps.bonding_curve,  // Store the bump
+            creator: ctx.accounts.authority.key(),
+            moderation_status: ModerationStatus::Active,
+            moderation_reason: 0,
+            reference_price: 0,
+            reference_slot: 0,
+            halted: false,
+            launch_slot: clock.slot,
+            launch_time: clock.unix_timestamp,
+            launch_protection,
+            presale_end_slot: 0,
+            auction_active: false,
+            spl_quote: false,
+            reserved: [0; CURVE_RESERVED_BYTES],
         };
         
         ctx.accounts.bonding_curve.set_inner(curve_params);
+
+        // Count the launch on the creator's profile (created on first launch)
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.creator = ctx.accounts.authority.key();
+        creator_profile.bump = ctx.bumps.creator_profile;
+        creator_profile.tokens_launched = creator_profile.tokens_launched.saturating_add(1);
+
+        // Charge the launch fee into the protocol fee vault
+        trade::transfer_lamports(
+            &ctx.accounts.authority.to_account_info(),
+            ctx.accounts.fee_vault.as_ref(),
+            &ctx.accounts.system_program,
+            ctx.accounts.program_config.creation_fee_lamports,
+        )?;
+
+        // Hand mint authority to the curve PDA so only the curve can mint
+        let set_authority_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::SetAuthority {
+                current_authority: ctx.accounts.authority.to_account_info(),
+                account_or_mint: ctx.accounts.token_mint.to_account_info(),
+            },
+        );
+        token::set_authority(
+            set_authority_ctx,
+            AuthorityType::MintTokens,
+            Some(ctx.accounts.bonding_curve.key()),
+        )?;
+
+        // Creator dev-buy in the same instruction, so nobody can trade first.
+        // It isn't subject to the creator's own launch protection.
+        if let Some(initial_buy_sol) = initial_buy_sol {
+            let creator_token_account = ctx.accounts.creator_token_account.as_ref()
+                .ok_or(ErrorCode::MissingCreatorTokenAccount)?;
+            let mut curve_trade = CurveTrade {
+                token_mint: &mut ctx.accounts.token_mint,
+                bonding_curve: &mut ctx.accounts.bonding_curve,
+                creator_profile: &mut ctx.accounts.creator_profile,
+                fee_collector: ctx.accounts.fee_vault.as_ref(),
+                token_program: &ctx.accounts.token_program,
+                system_program: &ctx.accounts.system_program,
+            };
+            curve_trade.buy(
+                &ctx.accounts.authority.to_account_info(),
+                &creator_token_account.to_account_info(),
+                initial_buy_sol,
+                0,
+                u64::from(ctx.accounts.program_config.trading_fee_bps),
+            )?;
+        }
+
+        // Start the circuit breaker window after the dev-buy so it doesn't
+        // count towards the first trader's price move
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.reference_price = BondingCurve::calculate_price(bonding_curve.total_liquidity)?;
+        bonding_curve.reference_slot = clock.slot;
         
         Ok(())
     }
@@ -78,465 +170,3434 @@ pub mod pump_fun {
         amount_in: u64,
         min_amount_out: u64,
         is_buy: bool,
+        deadline: Option<TradeDeadline>,
     ) -> Result<()> {
-        const TRADING_FEE_BPS: u64 = 100; // 1% = 100 basis points
-        
-        // Get current market cap (total_liquidity)
-        let current_market_cap = ctx.accounts.bonding_curve.total_liquidity;
-        
-        if is_buy {
-            // Calculate fee
-            let fee_amount = amount_in
-                .checked_mul(TRADING_FEE_BPS)
-                .unwrap()
-                .checked_div(10000)
-                .unwrap();
-            let amount_after_fee = amount_in.checked_sub(fee_amount).unwrap();
-            
-            // Calculate tokens to receive using bonding curve
-            let tokens_out = BondingCurve::calculate_tokens_out(
-                amount_after_fee, 
-                current_market_cap
-            )?;
-            require!(tokens_out >= min_amount_out, ErrorCode::SlippageExceeded);
-
-            // Check if we should transition to Raydium
-            if BondingCurve::should_transition_to_raydium(ctx.accounts.bonding_curve.current_supply) {
-                return Err(ErrorCode::TransitionToRaydium.into());
-            }
+        ctx.accounts.trade(ctx.bumps.user_trade_state, amount_in, min_amount_out, is_buy, deadline)?;
+        Ok(())
+    }
 
-            // Transfer SOL fee to fee collector
-            let fee_transfer_ctx = CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.user.to_account_info(),
-                    to: ctx.accounts.fee_collector.to_account_info(),
-                },
-            );
-            anchor_lang::system_program::transfer(fee_transfer_ctx, fee_amount)?;
-
-            // Transfer SOL from user to bonding curve
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.user.to_account_info(),
-                    to: ctx.accounts.bonding_curve.to_account_info(),
-                },
-            );
-            anchor_lang::system_program::transfer(cpi_ctx, amount_after_fee)?;
+    /// Sells `share_bps` of the user's token balance as it stands when the
+    /// transaction executes (10_000 sells everything), so a balance change
+    /// between quote and send can't leave dust or fail the sell. With
+    /// `close_account` the emptied token account is closed and its rent
+    /// returned to the user; that requires selling everything.
+    pub fn sell_by_share(
+        ctx: Context<TradeContext>,
+        share_bps: u16,
+        min_amount_out: u64,
+        close_account: bool,
+        deadline: Option<TradeDeadline>,
+    ) -> Result<()> {
+        require!(!close_account || share_bps == 10_000, ErrorCode::InvalidSellShare);
+        let amount_in = trade::share_of_balance(ctx.accounts.user_token_account.amount, share_bps)?;
 
-            // Mint tokens to user
-            let mint_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::MintTo {
-                    mint: ctx.accounts.token_mint.to_account_info(),
-                    to: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.bonding_curve.to_account_info(),
-                },
-            );
-            token::mint_to(mint_ctx, tokens_out)?;
-
-            // Update bonding curve state
-            ctx.accounts.bonding_curve.current_supply = ctx.accounts.bonding_curve.current_supply
-                .checked_add(tokens_out)
-                .unwrap();
-            ctx.accounts.bonding_curve.total_liquidity = ctx.accounts.bonding_curve.total_liquidity
-                .checked_add(amount_after_fee)
-                .unwrap();
-            
-        } else {
-            // Calculate SOL to receive using bonding curve
-            let sol_out = BondingCurve::calculate_sol_out(
-                amount_in, 
-                current_market_cap
+        let traded = ctx.accounts.trade(
+            ctx.bumps.user_trade_state,
+            amount_in,
+            min_amount_out,
+            false,
+            deadline,
+        )?;
+        if traded && close_account {
+            let user_info = ctx.accounts.user.to_account_info();
+            trade::close_token_account(
+                &ctx.accounts.user_token_account,
+                &user_info,
+                &user_info,
+                &ctx.accounts.token_program,
+                &[],
             )?;
-            require!(sol_out >= min_amount_out, ErrorCode::SlippageExceeded);
-
-            // Calculate fee
-            let fee_amount = sol_out
-                .checked_mul(TRADING_FEE_BPS)
-                .unwrap()
-                .checked_div(10000)
-                .unwrap();
-            let amount_after_fee = sol_out.checked_sub(fee_amount).unwrap();
-
-            // Get PDA signer seeds
-            let token_mint_key = ctx.accounts.token_mint.key();
-            let bump = ctx.accounts.bonding_curve.bump;
-            let seeds = &[
-                b"curve".as_ref(),
-                token_mint_key.as_ref(),
-                &[bump],
-            ];
-            let signer_seeds = &[&seeds[..]];
-
-            // Transfer SOL fee to fee collector
-            let fee_transfer_ctx = CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.bonding_curve.to_account_info(),
-                    to: ctx.accounts.fee_collector.to_account_info(),
-                },
-                signer_seeds,
-            );
-            anchor_lang::system_program::transfer(fee_transfer_ctx, fee_amount)?;
-
-            // Burn tokens from user
-            let burn_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Burn {
-                    mint: ctx.accounts.token_mint.to_account_info(),
-                    from: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            );
-            token::burn(burn_ctx, amount_in)?;
-
-            // Transfer SOL to user
-            let transfer_ctx = CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.bonding_curve.to_account_info(),
-                    to: ctx.accounts.user.to_account_info(),
-                },
-                signer_seeds,
-            );
-            anchor_lang::system_program::transfer(transfer_ctx, amount_after_fee)?;
-
-            // Update bonding curve state
-            ctx.accounts.bonding_curve.current_supply = ctx.accounts.bonding_curve.current_supply
-                .checked_sub(amount_in)
-                .unwrap();
-            ctx.accounts.bonding_curve.total_liquidity = ctx.accounts.bonding_curve.total_liquidity
-                .checked_sub(sol_out)
-                .unwrap();
         }
-        
+
         Ok(())
     }
 
-    pub fn initialize_treasury(
-        ctx: Context<InitializeTreasury>,
-        token_mint: Pubkey,
-    ) -> Result<()> {
-        // Initialize treasury PDA for this specific token
-        let treasury_bump = ctx.bumps.treasury_authority;
-        let treasury_seeds = &[
-            b"treasury",
-            token_mint.as_ref(),
-            &[treasury_bump]
-        ];
-        
-        // Create the treasury token account owned by the PDA
-        let cpi_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token::InitializeAccount {
-                account: ctx.accounts.treasury_token_account.to_account_info(),
-                mint: ctx.accounts.token_mint.to_account_info(),
-                authority: ctx.accounts.treasury_authority.to_account_info(),
-                rent: ctx.accounts.rent.to_account_info(),
-            },
+    /// Quotes a new curve in an allowed SPL mint instead of SOL and opens its
+    /// quote vault. Like `create_presale` it must run in the launch slot (in
+    /// the same transaction as `create_token`), before any trade, so the
+    /// curve never holds lamports of liquidity. Escrow-based flows (presale,
+    /// auction, limit orders, DCA and `batch_trade`) stay SOL-only.
+    pub fn set_quote_mint(ctx: Context<SetQuoteMint>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(
+            Clock::get()?.slot == bonding_curve.launch_slot
+                && bonding_curve.current_supply == 0
+                && bonding_curve.total_liquidity == 0
+                && bonding_curve.presale_end_slot == 0
+                && !bonding_curve.auction_active,
+            ErrorCode::QuoteMintLocked
         );
-        token::initialize_account(cpi_ctx)?;
+        bonding_curve.spl_quote = true;
+
+        emit!(QuoteMintSetEvent {
+            token_mint: ctx.accounts.token_mint.key(),
+            quote_mint: ctx.accounts.quote_mint.key(),
+            quote_vault: ctx.accounts.quote_vault.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
 
-    pub fn withdraw_fees(
-        ctx: Context<WithdrawFees>,
-        amount: u64,
+    /// `trade_token` for curves quoted in an SPL mint. Amounts on the quote
+    /// side are in the quote mint's base units and fees are paid in it.
+    pub fn trade_token_quote(
+        ctx: Context<QuoteTradeContext>,
+        amount_in: u64,
+        min_amount_out: u64,
+        is_buy: bool,
+        deadline: Option<TradeDeadline>,
     ) -> Result<()> {
-        require!(
-            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
-            ErrorCode::Unauthorized
-        );
-
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.fee_collector.to_account_info(),
-                to: ctx.accounts.authority.to_account_info(),
-            },
-        );
-        
-        anchor_lang::system_program::transfer(transfer_ctx, amount)?;
-        
+        ctx.accounts.trade(ctx.bumps.user_trade_state, amount_in, min_amount_out, is_buy, deadline)?;
         Ok(())
     }
 
-    pub fn initialize_program_config(
-        ctx: Context<InitializeProgramConfig>,
-        fee_collector: Pubkey,
-        admin: Pubkey,
+    /// `sell_by_share` for curves quoted in an SPL mint.
+    pub fn sell_by_share_quote(
+        ctx: Context<QuoteTradeContext>,
+        share_bps: u16,
+        min_amount_out: u64,
+        close_account: bool,
+        deadline: Option<TradeDeadline>,
     ) -> Result<()> {
-        ctx.accounts.program_config.fee_collector = fee_collector;
-        ctx.accounts.program_config.admin = admin;
+        require!(!close_account || share_bps == 10_000, ErrorCode::InvalidSellShare);
+        let amount_in = trade::share_of_balance(ctx.accounts.user_token_account.amount, share_bps)?;
+
+        let traded = ctx.accounts.trade(
+            ctx.bumps.user_trade_state,
+            amount_in,
+            min_amount_out,
+            false,
+            deadline,
+        )?;
+        if traded && close_account {
+            let user_info = ctx.accounts.user.to_account_info();
+            trade::close_token_account(
+                &ctx.accounts.user_token_account,
+                &user_info,
+                &user_info,
+                &ctx.accounts.token_program,
+                &[],
+            )?;
+        }
+
         Ok(())
     }
 
-    pub fn update_program_config(
-        ctx: Context<UpdateProgramConfig>,
-        new_fee_collector: Option<Pubkey>,
-        new_admin: Option<Pubkey>,
+    /// Commits the presale allowlist. Must run in the launch slot before any
+    /// trade (in practice, in the same transaction as `create_token`), so
+    /// nobody can buy before the presale is in place. Public trading opens
+    /// once `presale_slots` have passed.
+    pub fn create_presale(
+        ctx: Context<CreatePresale>,
+        merkle_root: [u8; 32],
+        presale_slots: u64,
     ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
         require!(
-            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
-            ErrorCode::Unauthorized
+            Clock::get()?.slot == bonding_curve.launch_slot
+                && bonding_curve.current_supply == 0
+                && presale_slots > 0
+                && presale_slots <= MAX_PRESALE_SLOTS
+                && !bonding_curve.auction_active,
+            ErrorCode::InvalidPresale
         );
 
-        if let Some(fee_collector) = new_fee_collector {
-            ctx.accounts.program_config.fee_collector = fee_collector;
-        }
-        if let Some(admin) = new_admin {
-            ctx.accounts.program_config.admin = admin;
-        }
+        bonding_curve.presale_end_slot = bonding_curve.launch_slot
+            .checked_add(presale_slots)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let presale = &mut ctx.accounts.presale;
+        presale.token_mint = ctx.accounts.token_mint.key();
+        presale.merkle_root = merkle_root;
+        presale.bump = ctx.bumps.presale;
+
+        emit!(PresaleCreatedEvent {
+            token_mint: presale.token_mint,
+            merkle_root,
+            end_slot: bonding_curve.presale_end_slot,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 
-    pub fn pause_trading(ctx: Context<PauseTrading>) -> Result<()> {
+    /// Buys during the presale. `max_allocation` and `proof` must match the
+    /// wallet's leaf in the committed allowlist.
+    pub fn presale_buy(
+        ctx: Context<PresaleBuy>,
+        amount_in: u64,
+        min_amount_out: u64,
+        max_allocation: u64,
+        proof: Vec<[u8; 32]>,
+        deadline: Option<TradeDeadline>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.program_config.trading_paused, ErrorCode::TradingPaused);
+        require!(!ctx.accounts.bonding_curve.halted, ErrorCode::MarketHalted);
+        let clock = Clock::get()?;
+        trade::require_before_deadline(deadline, &clock)?;
+        require!(clock.slot < ctx.accounts.bonding_curve.presale_end_slot, ErrorCode::PresaleClosed);
+
+        let user = ctx.accounts.user.key();
         require!(
-            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
-            ErrorCode::Unauthorized
+            ctx.accounts.presale.verify(&proof, Presale::leaf(&user, max_allocation)),
+            ErrorCode::NotOnAllowlist
         );
-        
-        ctx.accounts.program_config.trading_paused = true;
+
+        let allocation = &mut ctx.accounts.presale_allocation;
+        allocation.user = user;
+        allocation.token_mint = ctx.accounts.token_mint.key();
+        allocation.bump = ctx.bumps.presale_allocation;
+        allocation.record_spend(amount_in, max_allocation)?;
+
+        let fee_bps = ctx.accounts.bonding_curve.trading_fee_bps(
+            u64::from(ctx.accounts.program_config.trading_fee_bps),
+            clock.unix_timestamp,
+        );
+        let mut curve_trade = CurveTrade {
+            token_mint: &mut ctx.accounts.token_mint,
+            bonding_curve: &mut ctx.accounts.bonding_curve,
+            creator_profile: &mut ctx.accounts.creator_profile,
+            fee_collector: ctx.accounts.fee_vault.as_ref(),
+            token_program: &ctx.accounts.token_program,
+            system_program: &ctx.accounts.system_program,
+        };
+        curve_trade.buy(
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            amount_in,
+            min_amount_out,
+            fee_bps,
+        )?;
+
         Ok(())
     }
 
-    pub fn resume_trading(ctx: Context<ResumeTrading>) -> Result<()> {
+    /// Opens a batch auction for the first `duration_seconds` after launch.
+    /// Like the presale it must run in the launch slot before any trade, and
+    /// the curve stays closed to normal trading until the auction is finalized.
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        duration_seconds: i64,
+        max_raise: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
         require!(
-            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
-            ErrorCode::Unauthorized
+            clock.slot == bonding_curve.launch_slot
+                && bonding_curve.current_supply == 0
+                && duration_seconds > 0
+                && duration_seconds <= MAX_AUCTION_SECONDS
+                && bonding_curve.presale_end_slot == 0,
+            ErrorCode::InvalidAuction
         );
-        
-        ctx.accounts.program_config.trading_paused = false;
+        bonding_curve.auction_active = true;
+
+        let auction = &mut ctx.accounts.auction;
+        auction.token_mint = ctx.accounts.token_mint.key();
+        auction.end_time = clock.unix_timestamp
+            .checked_add(duration_seconds)
+            .ok_or(ErrorCode::Overflow)?;
+        auction.max_raise = max_raise;
+        auction.bump = ctx.bumps.auction;
+
+        emit!(AuctionCreatedEvent {
+            token_mint: auction.token_mint,
+            end_time: auction.end_time,
+            max_raise,
+            timestamp: clock.unix_timestamp,
+        });
+
         Ok(())
     }
 
-    pub fn update_trading_fee(
-        ctx: Context<UpdateTradingFee>,
-        new_fee_bps: u16,
-    ) -> Result<()> {
+    /// Adds `amount` lamports to the caller's bid. Bids can only grow.
+    pub fn auction_bid(ctx: Context<AuctionBidContext>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroAmount);
+        let auction = &mut ctx.accounts.auction;
         require!(
-            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
-            ErrorCode::Unauthorized
+            Clock::get()?.unix_timestamp < auction.end_time && !auction.finalized,
+            ErrorCode::AuctionClosed
         );
-        require!(new_fee_bps <= 1000, ErrorCode::InvalidFeePercentage); // Max 10%
-        
-        ctx.accounts.program_config.trading_fee_bps = new_fee_bps;
-        
-        emit!(ConfigUpdateEvent {
-            admin: ctx.accounts.program_config.admin,
-            fee_collector: ctx.accounts.program_config.fee_collector,
-            trading_fee_bps: new_fee_bps,
+
+        trade::transfer_lamports(
+            &ctx.accounts.user.to_account_info(),
+            &auction.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        auction.total_bid = auction.total_bid
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let bid = &mut ctx.accounts.bid;
+        bid.user = ctx.accounts.user.key();
+        bid.token_mint = ctx.accounts.token_mint.key();
+        bid.bump = ctx.bumps.bid;
+        bid.amount = bid.amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(AuctionBidEvent {
+            token_mint: bid.token_mint,
+            user: bid.user,
+            amount,
+            total_bid: auction.total_bid,
             timestamp: Clock::get()?.unix_timestamp,
         });
-        
+
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(name: String, symbol: String)]
-pub struct CreateTokenContext<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub program_config: Account<'info, ProgramConfig>,
-    
-    #[account(
-        init,
-        payer = authority,
-        mint::decimals = 9,
-        mint::authority = authority,
-    )]
-    pub token_mint: Account<'info, Mint>,
-    
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + TokenMetadata::SIZE,  // discriminator + metadata size
-        seeds = [b"metadata", token_mint.key().as_ref()],
-        bump
-    )]
-    pub token_metadata: Account<'info, TokenMetadata>,
-    
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + BondingCurveParams::SIZE,  // discriminator + params size
-        seeds = [b"curve", token_mint.key().as_ref()],
-        bump
-    )]
-    pub bonding_curve: Account<'info, BondingCurveParams>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    /// Permissionless. Buys the filled part of all bids from the curve in a
+    /// single trade, so every bidder gets the same price, and opens the curve
+    /// to normal trading.
+    pub fn finalize_auction(ctx: Context<FinalizeAuction>) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(!ctx.accounts.program_config.trading_paused, ErrorCode::TradingPaused);
+        require!(!ctx.accounts.auction.finalized, ErrorCode::AuctionAlreadyFinalized);
+        require!(clock.unix_timestamp >= ctx.accounts.auction.end_time, ErrorCode::AuctionNotEnded);
 
-#[derive(Accounts)]
-pub struct CreatePoolContext<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        // A token delisted during the auction can't be bought, so every bid is refunded
+        let filled_sol = if ctx.accounts.bonding_curve.moderation_status == ModerationStatus::Delisted {
+            0
+        } else {
+            ctx.accounts.auction.fill_amount()
+        };
+        let mut tokens_sold = 0;
+        if filled_sol > 0 {
+            let auction_info = ctx.accounts.auction.to_account_info();
+            let mut curve_trade = CurveTrade {
+                token_mint: &mut ctx.accounts.token_mint,
+                bonding_curve: &mut ctx.accounts.bonding_curve,
+                creator_profile: &mut ctx.accounts.creator_profile,
+                fee_collector: ctx.accounts.fee_vault.as_ref(),
+                token_program: &ctx.accounts.token_program,
+                system_program: &ctx.accounts.system_program,
+            };
+            tokens_sold = curve_trade.buy(
+                &auction_info,
+                &ctx.accounts.auction_token_account.to_account_info(),
+                filled_sol,
+                0,
+                u64::from(ctx.accounts.program_config.trading_fee_bps),
+            )?;
+        }
 
-    #[account(
-        init,
-        payer = authority,
-        token::mint = token_mint,
-        token::authority = pool_authority,
-    )]
-    pub pool_token_account: Account<'info, TokenAccount>,
+        let auction = &mut ctx.accounts.auction;
+        auction.filled_sol = filled_sol;
+        auction.tokens_sold = tokens_sold;
+        auction.finalized = true;
 
-    pub token_mint: Account<'info, Mint>,
+        // Normal trading starts from the clearing price
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.auction_active = false;
+        bonding_curve.reference_price = BondingCurve::calculate_price(bonding_curve.total_liquidity)?;
+        bonding_curve.reference_slot = clock.slot;
 
-    /// CHECK: This is the PDA that will own the pool
-    #[account(
-        seeds = [b"pool", token_mint.key().as_ref()],
-        bump
-    )]
-    pub pool_authority: AccountInfo<'info>,
+        emit!(AuctionFinalizedEvent {
+            token_mint: auction.token_mint,
+            total_bid: auction.total_bid,
+            filled_sol,
+            tokens_sold,
+            timestamp: clock.unix_timestamp,
+        });
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct TradeContext<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        mut,
-        token::mint = token_mint,
-        token::authority = treasury_authority
-    )]
-    pub treasury_token_account: Account<'info, TokenAccount>,
-    
-    pub token_mint: Account<'info, Mint>,
-    
-    /// CHECK: PDA that owns the treasury
-    #[account(
-        seeds = [b"treasury", token_mint.key().as_ref()],
-        bump
-    )]
-    pub treasury_authority: AccountInfo<'info>,
-    
-    #[account(
-        mut,
-        seeds = [b"curve", token_mint.key().as_ref()],
-        bump,
-    )]
-    pub bonding_curve: Account<'info, BondingCurveParams>,
+    /// Pays out a bid after finalization: its share of the tokens plus a
+    /// refund of the unfilled SOL. Closes the bid account. The last bid to
+    /// settle also takes the rounding dust, and the auction and its token
+    /// account are closed with their rent going to the creator.
+    pub fn settle_auction_bid(ctx: Context<SettleAuctionBid>) -> Result<()> {
+        require!(ctx.accounts.auction.finalized, ErrorCode::AuctionNotFinalized);
 
-    /// CHECK: Account that receives trading fees
-    #[account(
-        mut,
-        constraint = fee_collector.key() == program_config.fee_collector
-    )]
-    pub fee_collector: AccountInfo<'info>,
-    
-    pub program_config: Account<'info, ProgramConfig>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        let (mut tokens, refund) = ctx.accounts.auction.allocation(ctx.accounts.bid.amount)?;
+        let last = ctx.accounts.auction.record_settlement(ctx.accounts.bid.amount)?;
+        if last {
+            tokens = ctx.accounts.auction_token_account.amount;
+        }
 
-#[derive(Accounts)]
-#[instruction(token_mint: Pubkey)]
-pub struct InitializeTreasury<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = authority,
-        token::mint = token_mint,
-        token::authority = treasury_authority,
-    )]
-    pub treasury_token_account: Account<'info, TokenAccount>,
-    
-    pub token_mint: Account<'info, Mint>,
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let seeds = &[
+            b"auction".as_ref(),
+            token_mint_key.as_ref(),
+            &[ctx.accounts.auction.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        let auction_info = ctx.accounts.auction.to_account_info();
+        if tokens > 0 {
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.auction_token_account.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: auction_info.clone(),
+                },
+                signer_seeds,
+            );
+            token::transfer(transfer_ctx, tokens)?;
+        }
+        trade::transfer_lamports(
+            &auction_info,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program,
+            refund,
+        )?;
+
+        if last {
+            let creator = ctx.accounts.creator.to_account_info();
+            trade::close_token_account(
+                &ctx.accounts.auction_token_account,
+                &creator,
+                &auction_info,
+                &ctx.accounts.token_program,
+                signer_seeds,
+            )?;
+            ctx.accounts.auction.close(creator)?;
+        }
+
+        emit!(AuctionSettledEvent {
+            token_mint: token_mint_key,
+            user: ctx.accounts.user.key(),
+            tokens,
+            refund,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Places a limit or trigger order. Buys escrow `amount` lamports in the
+    /// order PDA, sells escrow `amount` tokens in the order's token account.
+    /// The owner also escrows `KEEPER_FEE_LAMPORTS` for whoever fills it.
+    /// `min_amount_out` guards the fill against slippage.
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        order_id: u64,
+        side: OrderSide,
+        amount: u64,
+        limit_price: u64,
+        stop_price: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroAmount);
+        LimitOrder::validate_prices(side, limit_price, stop_price)?;
+
+        let escrow_lamports = match (side, &ctx.accounts.order_token_account) {
+            (OrderSide::Buy, None) => amount.checked_add(KEEPER_FEE_LAMPORTS).ok_or(ErrorCode::Overflow)?,
+            (OrderSide::Sell, Some(order_token_account)) => {
+                let transfer_ctx = CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.owner_token_account.to_account_info(),
+                        to: order_token_account.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                );
+                token::transfer(transfer_ctx, amount)?;
+                KEEPER_FEE_LAMPORTS
+            }
+            _ => return err!(ErrorCode::InvalidOrderEscrow),
+        };
+        trade::transfer_lamports(
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.order.to_account_info(),
+            &ctx.accounts.system_program,
+            escrow_lamports,
+        )?;
+
+        let order = &mut ctx.accounts.order;
+        order.owner = ctx.accounts.owner.key();
+        order.token_mint = ctx.accounts.token_mint.key();
+        order.order_id = order_id;
+        order.side = side;
+        order.amount = amount;
+        order.limit_price = limit_price;
+        order.stop_price = stop_price;
+        order.min_amount_out = min_amount_out;
+        order.created_at = Clock::get()?.unix_timestamp;
+        order.bump = ctx.bumps.order;
+
+        emit!(OrderPlacedEvent {
+            order: order.key(),
+            owner: order.owner,
+            token_mint: order.token_mint,
+            side,
+            amount,
+            limit_price,
+            stop_price,
+            timestamp: order.created_at,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless. Executes an order once the curve's spot price has
+    /// crossed its limit or stop, pays the keeper fee to the caller and closes the
+    /// order, returning its rent to the owner. A fill that trips the circuit
+    /// breaker halts the market and leaves the order open.
+    pub fn fill_order(ctx: Context<FillOrder>) -> Result<()> {
+        let clock = Clock::get()?;
+        let order = &ctx.accounts.order;
+        let Some((spot_price, fee_bps)) = trade::prepare_order_fill(
+            ctx.accounts.token_mint.key(),
+            order,
+            &mut ctx.accounts.bonding_curve,
+            &ctx.accounts.program_config,
+            &clock,
+        )?
+        else {
+            return Ok(());
+        };
+        let is_buy = order.side == OrderSide::Buy;
+
+        let owner_trade_state = &mut ctx.accounts.owner_trade_state;
+        owner_trade_state.user = order.owner;
+        owner_trade_state.token_mint = ctx.accounts.token_mint.key();
+        owner_trade_state.bump = ctx.bumps.owner_trade_state;
+        if is_buy {
+            trade::record_user_buy(
+                &ctx.accounts.program_config,
+                &ctx.accounts.bonding_curve,
+                owner_trade_state,
+                order.amount,
+                clock.slot,
+            )?;
+        }
+
+        let order_info = order.to_account_info();
+        let owner_key = order.owner;
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let order_id = order.order_id.to_le_bytes();
+        let seeds = &[
+            b"order".as_ref(),
+            token_mint_key.as_ref(),
+            owner_key.as_ref(),
+            order_id.as_ref(),
+            &[order.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        let amount = order.amount;
+        let min_amount_out = order.min_amount_out;
+
+        let mut curve_trade = CurveTrade {
+            token_mint: &mut ctx.accounts.token_mint,
+            bonding_curve: &mut ctx.accounts.bonding_curve,
+            creator_profile: &mut ctx.accounts.creator_profile,
+            fee_collector: ctx.accounts.fee_vault.as_ref(),
+            token_program: &ctx.accounts.token_program,
+            system_program: &ctx.accounts.system_program,
+        };
+        let amount_out = if is_buy {
+            curve_trade.buy(
+                &order_info,
+                &ctx.accounts.owner_token_account.to_account_info(),
+                amount,
+                min_amount_out,
+                fee_bps,
+            )?
+        } else {
+            let order_token_account = ctx.accounts.order_token_account.as_ref()
+                .ok_or(ErrorCode::InvalidOrderEscrow)?;
+            curve_trade.sell(
+                &order_token_account.to_account_info(),
+                &order_info,
+                signer_seeds,
+                &ctx.accounts.owner.to_account_info(),
+                amount,
+                min_amount_out,
+                fee_bps,
+            )?
+        };
+
+        trade::transfer_lamports(
+            &order_info,
+            &ctx.accounts.keeper.to_account_info(),
+            &ctx.accounts.system_program,
+            KEEPER_FEE_LAMPORTS,
+        )?;
+        if let Some(order_token_account) = ctx.accounts.order_token_account.as_mut() {
+            trade::sweep_and_close_token_account(
+                order_token_account,
+                &ctx.accounts.owner_token_account.to_account_info(),
+                &ctx.accounts.owner,
+                &order_info,
+                &ctx.accounts.token_program,
+                signer_seeds,
+            )?;
+        }
+
+        emit!(OrderFilledEvent {
+            order: order_info.key(),
+            keeper: ctx.accounts.keeper.key(),
+            spot_price,
+            amount_in: amount,
+            amount_out,
+            timestamp: clock.unix_timestamp,
+        });
+
+        ctx.accounts.order.close(ctx.accounts.owner.to_account_info())
+
+    }
+
+    /// Cancels an order and returns its escrow and rent to the owner.
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        let order = &ctx.accounts.order;
+        let order_info = order.to_account_info();
+        let owner_key = order.owner;
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let order_id = order.order_id.to_le_bytes();
+        let seeds = &[
+            b"order".as_ref(),
+            token_mint_key.as_ref(),
+            owner_key.as_ref(),
+            order_id.as_ref(),
+            &[order.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        // Buy escrow is in the order's lamports and goes back with `close`;
+        // a sell's tokens are swept back with anything else in its escrow
+        require!(
+            order.side == OrderSide::Buy || ctx.accounts.order_token_account.is_some(),
+            ErrorCode::InvalidOrderEscrow
+        );
+        if let Some(order_token_account) = ctx.accounts.order_token_account.as_mut() {
+            trade::sweep_and_close_token_account(
+                order_token_account,
+                &ctx.accounts.owner_token_account.to_account_info(),
+                &ctx.accounts.owner.to_account_info(),
+                &order_info,
+                &ctx.accounts.token_program,
+                signer_seeds,
+            )?;
+        }
+
+        emit!(OrderCancelledEvent {
+            order: order_info.key(),
+            owner: owner_key,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Opens a DCA vault for `token_mint` with an initial `deposit`. The first
+    /// period is due immediately.
+    pub fn create_dca_vault(
+        ctx: Context<CreateDcaVault>,
+        amount_per_period: u64,
+        interval_seconds: i64,
+        max_price: u64,
+        deposit: u64,
+    ) -> Result<()> {
+        require!(
+            amount_per_period > 0 && interval_seconds >= MIN_DCA_INTERVAL_SECONDS,
+            ErrorCode::InvalidDcaSchedule
+        );
+
+        trade::transfer_lamports(
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.system_program,
+            deposit,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.owner = ctx.accounts.owner.key();
+        vault.token_mint = ctx.accounts.token_mint.key();
+        vault.amount_per_period = amount_per_period;
+        vault.interval_seconds = interval_seconds;
+        vault.max_price = max_price;
+        vault.next_execution = Clock::get()?.unix_timestamp;
+        vault.balance = deposit;
+        vault.paused = false;
+        vault.bump = ctx.bumps.vault;
+
+        emit!(DcaVaultCreatedEvent {
+            vault: vault.key(),
+            owner: vault.owner,
+            token_mint: vault.token_mint,
+            amount_per_period,
+            interval_seconds,
+            max_price,
+            timestamp: vault.next_execution,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_dca(ctx: Context<UpdateDcaVault>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroAmount);
+        trade::transfer_lamports(
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.balance = vault.balance.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    pub fn withdraw_dca(ctx: Context<UpdateDcaVault>, amount: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.balance = vault.balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::DcaInsufficientBalance)?;
+        trade::transfer_lamports(
+            &vault.to_account_info(),
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )
+    }
+
+    pub fn set_dca_paused(ctx: Context<UpdateDcaVault>, paused: bool) -> Result<()> {
+        ctx.accounts.vault.paused = paused;
+        Ok(())
+    }
+
+    /// Closes the vault, returning the unspent balance and rent to the owner.
+    pub fn close_dca_vault(_ctx: Context<CloseDcaVault>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Permissionless. Runs one due period of a DCA vault through the curve,
+    /// sending the tokens to the owner and the keeper fee to the caller.
+    pub fn execute_dca(ctx: Context<ExecuteDca>) -> Result<()> {
+        let clock = Clock::get()?;
+        trade::require_market_open(&ctx.accounts.program_config, &ctx.accounts.bonding_curve, clock.slot)?;
+
+        let spot_price = BondingCurve::calculate_price(ctx.accounts.bonding_curve.total_liquidity)?;
+        let cost = ctx.accounts.vault.check_due(clock.unix_timestamp, spot_price, KEEPER_FEE_LAMPORTS)?;
+        let amount = ctx.accounts.vault.amount_per_period;
+
+        let fee_bps = ctx.accounts.bonding_curve.trading_fee_bps(
+            u64::from(ctx.accounts.program_config.trading_fee_bps),
+            clock.unix_timestamp,
+        );
+        // `check_due` has already confirmed the vault can pay for the period
+        if trade::halt_if_breaker_trips(
+            ctx.accounts.token_mint.key(),
+            &mut ctx.accounts.bonding_curve,
+            &ctx.accounts.program_config,
+            true,
+            amount,
+            amount,
+            fee_bps,
+            &clock,
+        )? {
+            return Ok(());
+        }
+
+        let owner_trade_state = &mut ctx.accounts.owner_trade_state;
+        owner_trade_state.user = ctx.accounts.vault.owner;
+        owner_trade_state.token_mint = ctx.accounts.token_mint.key();
+        owner_trade_state.bump = ctx.bumps.owner_trade_state;
+        trade::record_user_buy(
+            &ctx.accounts.program_config,
+            &ctx.accounts.bonding_curve,
+            owner_trade_state,
+            amount,
+            clock.slot,
+        )?;
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let mut curve_trade = CurveTrade {
+            token_mint: &mut ctx.accounts.token_mint,
+            bonding_curve: &mut ctx.accounts.bonding_curve,
+            creator_profile: &mut ctx.accounts.creator_profile,
+            fee_collector: ctx.accounts.fee_vault.as_ref(),
+            token_program: &ctx.accounts.token_program,
+            system_program: &ctx.accounts.system_program,
+        };
+        let tokens_out = curve_trade.buy(
+            &vault_info,
+            &ctx.accounts.owner_token_account.to_account_info(),
+            amount,
+            0,
+            fee_bps,
+        )?;
+        trade::transfer_lamports(
+            &vault_info,
+            &ctx.accounts.keeper.to_account_info(),
+            &ctx.accounts.system_program,
+            KEEPER_FEE_LAMPORTS,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.balance = vault.balance
+            .checked_sub(cost)
+            .ok_or(ErrorCode::Overflow)?;
+        vault.next_execution = clock.unix_timestamp
+            .checked_add(vault.interval_seconds)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(DcaExecutedEvent {
+            vault: vault.key(),
+            keeper: ctx.accounts.keeper.key(),
+            spot_price,
+            amount_in: amount,
+            tokens_out,
+            next_execution: vault.next_execution,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Runs up to `MAX_BATCH_LEGS` trades in one instruction; if any leg
+    /// fails, none of them happen. A leg that trips the circuit breaker halts
+    /// its market and is skipped instead of failing the batch. Each leg takes `BATCH_LEG_ACCOUNTS`
+    /// accounts from `remaining_accounts`, in leg order: token mint, bonding
+    /// curve, creator profile, user token account (must exist) and user
+    /// trade state (created if missing).
+    pub fn batch_trade<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchTrade<'info>>,
+        legs: Vec<TradeLeg>,
+        deadline: Option<TradeDeadline>,
+    ) -> Result<()> {
+        trade::require_valid_batch(legs.len(), ctx.remaining_accounts.len())?;
+
+        let clock = Clock::get()?;
+        trade::require_before_deadline(deadline, &clock)?;
+        let user = ctx.accounts.user.to_account_info();
+        for (leg, accounts) in legs.iter().zip(ctx.remaining_accounts.chunks(BATCH_LEG_ACCOUNTS)) {
+            trade::execute_batch_leg(
+                leg,
+                accounts,
+                &user,
+                &ctx.accounts.program_config,
+                ctx.accounts.fee_vault.as_ref(),
+                &ctx.accounts.token_program,
+                &ctx.accounts.system_program,
+                &clock,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Permissionless health check of a curve's reserve and supply. Reports
+    /// through `CurveAuditEvent` rather than failing, so discrepancies are
+    /// visible to monitoring.
+    pub fn audit_curve(ctx: Context<AuditCurve>) -> Result<()> {
+        let audit = trade::audit_curve(
+            &ctx.accounts.bonding_curve,
+            &ctx.accounts.token_mint,
+            ctx.accounts.quote_vault.as_deref(),
+        )?;
+
+        emit!(CurveAuditEvent {
+            token_mint: ctx.accounts.token_mint.key(),
+            reserve: audit.reserve,
+            required_reserve: audit.required_reserve,
+            mint_supply: audit.mint_supply,
+            expected_supply: audit.expected_supply,
+            solvent: audit.is_solvent(),
+            supply_matches: audit.supply_matches(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_creator_profile(
+        ctx: Context<UpdateCreatorProfile>,
+        display_name: Option<String>,
+        avatar_uri: Option<String>,
+    ) -> Result<()> {
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.creator = ctx.accounts.creator.key();
+        creator_profile.bump = ctx.bumps.creator_profile;
+
+        if let Some(display_name) = display_name {
+            require!(display_name.len() <= MAX_DISPLAY_NAME_LEN, ErrorCode::DisplayNameTooLong);
+            creator_profile.display_name = display_name;
+        }
+        if let Some(avatar_uri) = avatar_uri {
+            require!(avatar_uri.len() <= MAX_AVATAR_URI_LEN, ErrorCode::AvatarUriTooLong);
+            creator_profile.avatar_uri = avatar_uri;
+        }
+        Ok(())
+    }
+
+    pub fn reserve_symbol(
+        ctx: Context<ReserveSymbol>,
+        normalized_symbol: String,
+    ) -> Result<()> {
+        // The registry PDA is seeded by the client-supplied key, so make sure
+        // it really is this token's symbol
+        require!(
+            SymbolRegistry::normalize(&ctx.accounts.token_metadata.symbol)? == normalized_symbol,
+            ErrorCode::SymbolMismatch
+        );
+
+        let registered_at = Clock::get()?.unix_timestamp;
+        ctx.accounts.symbol_registry.set_inner(SymbolRegistry {
+            symbol: normalized_symbol.clone(),
+            token_mint: ctx.accounts.token_mint.key(),
+            creator: ctx.accounts.creator.key(),
+            registered_at,
+            bump: ctx.bumps.symbol_registry,
+        });
+
+        emit!(SymbolReservedEvent {
+            symbol: normalized_symbol,
+            token_mint: ctx.accounts.token_mint.key(),
+            creator: ctx.accounts.creator.key(),
+            timestamp: registered_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn release_symbol(ctx: Context<ReleaseSymbol>) -> Result<()> {
+        admin::require_role(
+            &ctx.accounts.program_config,
+            ctx.accounts.access_control.as_deref(),
+            &ctx.accounts.authority.key(),
+            Role::Moderator,
+        )?;
+
+        emit!(SymbolReleasedEvent {
+            symbol: ctx.accounts.symbol_registry.symbol.clone(),
+            token_mint: ctx.accounts.symbol_registry.token_mint,
+            released_by: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_moderation_status(
+        ctx: Context<SetModerationStatus>,
+        status: ModerationStatus,
+        reason_code: u16,
+    ) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        admin::require_role(
+            &ctx.accounts.program_config,
+            ctx.accounts.access_control.as_deref(),
+            &authority,
+            Role::Moderator,
+        )?;
+
+        ctx.accounts.bonding_curve.moderation_status = status;
+        ctx.accounts.bonding_curve.moderation_reason = reason_code;
+
+        emit!(ModerationEvent {
+            token_mint: ctx.accounts.token_mint.key(),
+            status,
+            reason_code,
+            moderator: authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn reset_circuit_breaker(ctx: Context<ResetCircuitBreaker>) -> Result<()> {
+        admin::require_role(
+            &ctx.accounts.program_config,
+            ctx.accounts.access_control.as_deref(),
+            &ctx.accounts.authority.key(),
+            Role::Moderator,
+        )?;
+
+        let clock = Clock::get()?;
+        let price = ctx.accounts.bonding_curve.reset_circuit_breaker(clock.slot)?;
+
+        emit!(CircuitBreakerResetEvent {
+            token_mint: ctx.accounts.token_mint.key(),
+            reset_by: ctx.accounts.authority.key(),
+            price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_sandwich_protection(
+        ctx: Context<UpdateSandwichProtection>,
+        policy: SameSlotSellPolicy,
+        surcharge_bps: u16,
+        max_buy_per_slot: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::update_sandwich_protection(
+            &mut ctx.accounts.program_config,
+            policy,
+            surcharge_bps,
+            max_buy_per_slot,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn update_circuit_breaker(
+        ctx: Context<UpdateCircuitBreaker>,
+        threshold_bps: u16,
+        window_slots: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::update_circuit_breaker(&mut ctx.accounts.program_config, threshold_bps, window_slots)
+    }
+
+    /// Adds `quote_mint` to the SPL mints new curves may be quoted in.
+    pub fn allow_quote_mint(ctx: Context<AllowQuoteMint>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        let entry = admin::allow_quote_mint(&ctx.accounts.quote_mint, ctx.bumps.allowed_quote_mint)?;
+        ctx.accounts.allowed_quote_mint.set_inner(entry);
+        Ok(())
+    }
+
+    /// Removes a quote mint from the allowlist. Curves already quoted in it
+    /// keep trading.
+    pub fn disallow_quote_mint(ctx: Context<DisallowQuoteMint>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::disallow_quote_mint(ctx.accounts.allowed_quote_mint.mint)
+    }
+
+    pub fn initialize_treasury(
+        ctx: Context<InitializeTreasury>,
+        _token_mint: Pubkey,
+    ) -> Result<()> {
+        // Create the treasury token account owned by the PDA
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::InitializeAccount {
+                account: ctx.accounts.treasury_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                authority: ctx.accounts.treasury_authority.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+            },
+        );
+        token::initialize_account(cpi_ctx)?;
+
+        Ok(())
+    }
+
+    pub fn withdraw_fees(
+        ctx: Context<WithdrawFees>,
+        amount: u64,
+    ) -> Result<()> {
+        admin::require_role(
+            &ctx.accounts.program_config,
+            ctx.accounts.access_control.as_deref(),
+            &ctx.accounts.authority.key(),
+            Role::TreasuryWithdrawer,
+        )?;
+
+        admin::withdraw_fees(
+            ctx.accounts.fee_vault.as_ref(),
+            &ctx.accounts.authority.to_account_info(),
+            amount,
+        )
+    }
+
+    /// Creates the program-owned vault that collects SOL fees. Anyone may pay
+    /// for it; only `withdraw_fees` can move lamports out.
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        ctx.accounts.fee_vault.set_inner(FeeVault {
+            bump: ctx.bumps.fee_vault,
+        });
+        Ok(())
+    }
+
+    pub fn initialize_program_config(
+        ctx: Context<InitializeProgramConfig>,
+        fee_collector: Pubkey,
+        admin: Pubkey,
+        timelock_delay: i64,
+    ) -> Result<()> {
+        ConfigChange::TimelockDelay { new_delay: timelock_delay }.validate()?;
+
+        ctx.accounts.program_config.version = CURRENT_ACCOUNT_VERSION;
+        ctx.accounts.program_config.fee_collector = fee_collector;
+        ctx.accounts.program_config.admin = admin;
+        ctx.accounts.program_config.bump = ctx.bumps.program_config;
+        ctx.accounts.program_config.trading_fee_bps = DEFAULT_TRADING_FEE_BPS;
+        ctx.accounts.program_config.timelock_delay = timelock_delay;
+        Ok(())
+    }
+
+    pub fn update_program_config(
+        ctx: Context<UpdateProgramConfig>,
+        new_fee_collector: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::update_program_config(
+            &mut ctx.accounts.program_config,
+            new_fee_collector,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn propose_admin(
+        ctx: Context<ProposeAdmin>,
+        new_admin: Pubkey,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::propose_admin(&mut ctx.accounts.program_config, new_admin, Clock::get()?.unix_timestamp)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        admin::accept_admin(
+            &mut ctx.accounts.program_config,
+            ctx.accounts.authority.key(),
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn cancel_admin_transfer(ctx: Context<CancelAdminTransfer>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::cancel_admin_transfer(&mut ctx.accounts.program_config, Clock::get()?.unix_timestamp)
+    }
+
+    pub fn pause_trading(ctx: Context<PauseTrading>) -> Result<()> {
+        admin::require_role(
+            &ctx.accounts.program_config,
+            ctx.accounts.access_control.as_deref(),
+            &ctx.accounts.authority.key(),
+            Role::Pauser,
+        )?;
+        
+        admin::set_trading_paused(&mut ctx.accounts.program_config, true)
+    }
+
+    pub fn resume_trading(ctx: Context<ResumeTrading>) -> Result<()> {
+        admin::require_role(
+            &ctx.accounts.program_config,
+            ctx.accounts.access_control.as_deref(),
+            &ctx.accounts.authority.key(),
+            Role::Pauser,
+        )?;
+        
+        admin::set_trading_paused(&mut ctx.accounts.program_config, false)
+    }
+
+    pub fn update_trading_fee(
+        ctx: Context<UpdateTradingFee>,
+        new_fee_bps: u16,
+    ) -> Result<()> {
+        admin::require_role(
+            &ctx.accounts.program_config,
+            ctx.accounts.access_control.as_deref(),
+            &ctx.accounts.authority.key(),
+            Role::FeeManager,
+        )?;
+        
+        admin::queue_change(
+            &mut ctx.accounts.program_config,
+            ConfigChange::TradingFee { new_fee_bps },
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn update_creation_fee(
+        ctx: Context<UpdateCreationFee>,
+        new_fee_lamports: u64,
+    ) -> Result<()> {
+        admin::require_role(
+            &ctx.accounts.program_config,
+            ctx.accounts.access_control.as_deref(),
+            &ctx.accounts.authority.key(),
+            Role::FeeManager,
+        )?;
+
+        admin::queue_change(
+            &mut ctx.accounts.program_config,
+            ConfigChange::CreationFee { new_fee_lamports },
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn update_timelock_delay(
+        ctx: Context<UpdateTimelockDelay>,
+        new_delay: i64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::queue_change(
+            &mut ctx.accounts.program_config,
+            ConfigChange::TimelockDelay { new_delay },
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChange>, id: u64) -> Result<()> {
+        admin::execute_change(&mut ctx.accounts.program_config, id, Clock::get()?.unix_timestamp)
+    }
+
+    pub fn cancel_config_change(ctx: Context<CancelConfigChange>, id: u64) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::cancel_change(&mut ctx.accounts.program_config, id, Clock::get()?.unix_timestamp)
+    }
+
+    pub fn initialize_access_control(ctx: Context<InitializeAccessControl>) -> Result<()> {
+        ctx.accounts.access_control.set_inner(AccessControl {
+            members: Vec::new(),
+            bump: ctx.bumps.access_control,
+        });
+        Ok(())
+    }
+
+    pub fn grant_role(ctx: Context<UpdateRoles>, member: Pubkey, role: Role) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::grant_role(&mut ctx.accounts.access_control, member, role, Clock::get()?.unix_timestamp)
+    }
+
+    pub fn revoke_role(ctx: Context<UpdateRoles>, member: Pubkey, role: Role) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+
+        admin::revoke_role(
+            &ctx.accounts.program_config,
+            &mut ctx.accounts.access_control,
+            member,
+            role,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    /// Creates the multisig and proposes its PDA as the new admin, replacing
+    /// any pending transfer. Like any admin transfer it goes through the
+    /// timelock: the signers take over by executing an `AcceptAdmin` proposal
+    /// once the delay has passed. Nobody can sign for the PDA directly, so
+    /// from then on admin actions only go through executed proposals.
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.program_config.admin,
+            ErrorCode::Unauthorized
+        );
+        Multisig::validate(&signers, threshold)?;
+
+        ctx.accounts.multisig.set_inner(Multisig {
+            signers: signers.clone(),
+            threshold,
+            proposal_count: 0,
+            pending_update: None,
+            bump: ctx.bumps.multisig,
+        });
+
+        let now = Clock::get()?.unix_timestamp;
+        admin::propose_admin(&mut ctx.accounts.program_config, ctx.accounts.multisig.key(), now)?;
+
+        emit!(MultisigUpdatedEvent {
+            multisig: ctx.accounts.multisig.key(),
+            signers,
+            threshold,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        action: AdminAction,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let proposer = ctx.accounts.proposer.key();
+        require!(multisig.is_signer(&proposer), ErrorCode::NotMultisigSigner);
+        if let AdminAction::UpdateMultisig { signers, threshold } = &action {
+            Multisig::validate(signers, *threshold)?;
+        }
+
+        let index = multisig.proposal_count;
+        multisig.proposal_count = index.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+        let created_at = Clock::get()?.unix_timestamp;
+        ctx.accounts.proposal.set_inner(Proposal {
+            index,
+            proposer,
+            action: action.clone(),
+            approvals: vec![proposer],
+            executed: false,
+            created_at,
+            bump: ctx.bumps.proposal,
+        });
+
+        emit!(ProposalCreatedEvent {
+            proposal: ctx.accounts.proposal.key(),
+            index,
+            proposer,
+            action,
+            timestamp: created_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let proposal = &mut ctx.accounts.proposal;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(!proposal.is_expired(now), ErrorCode::ProposalExpired);
+
+        ctx.accounts.multisig.approve(proposal, signer)?;
+
+        emit!(ProposalApprovedEvent {
+            proposal: proposal.key(),
+            signer,
+            approvals: ctx.accounts.multisig.count_approvals(proposal) as u8,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Closes a pending proposal, refunding its rent to the proposer. The
+    /// proposer can cancel at any time; once expired, anyone can clean it up.
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        let proposal = &ctx.accounts.proposal;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(
+            authority == proposal.proposer || proposal.is_expired(now),
+            ErrorCode::Unauthorized
+        );
+
+        emit!(ProposalCancelledEvent {
+            proposal: proposal.key(),
+            cancelled_by: authority,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        let program_config = &mut ctx.accounts.program_config;
+        let now = Clock::get()?.unix_timestamp;
+        // Until the multisig has accepted the admin transfer, that's all it can do
+        require!(
+            program_config.admin == multisig.key() || proposal.action == AdminAction::AcceptAdmin,
+            ErrorCode::MultisigNotAdmin
+        );
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(!proposal.is_expired(now), ErrorCode::ProposalExpired);
+        require!(
+            multisig.count_approvals(proposal) >= multisig.threshold as usize,
+            ErrorCode::ThresholdNotMet
+        );
+
+        proposal.executed = true;
+
+        match proposal.action.clone() {
+            AdminAction::UpdateProgramConfig { new_fee_collector } => {
+                admin::update_program_config(program_config, new_fee_collector, now)?;
+            }
+            AdminAction::PauseTrading => admin::set_trading_paused(program_config, true)?,
+            AdminAction::ResumeTrading => admin::set_trading_paused(program_config, false)?,
+            AdminAction::UpdateTradingFee { new_fee_bps } => {
+                admin::queue_change(program_config, ConfigChange::TradingFee { new_fee_bps }, now)?;
+            }
+            AdminAction::UpdateCreationFee { new_fee_lamports } => {
+                admin::queue_change(
+                    program_config,
+                    ConfigChange::CreationFee { new_fee_lamports },
+                    now,
+                )?;
+            }
+            AdminAction::UpdateTimelockDelay { new_delay } => {
+                admin::queue_change(program_config, ConfigChange::TimelockDelay { new_delay }, now)?;
+            }
+            AdminAction::CancelConfigChange { id } => admin::cancel_change(program_config, id, now)?,
+            AdminAction::UpdateCircuitBreaker { threshold_bps, window_slots } => {
+                admin::update_circuit_breaker(program_config, threshold_bps, window_slots)?;
+            }
+            AdminAction::UpdateSandwichProtection { policy, surcharge_bps, max_buy_per_slot } => {
+                admin::update_sandwich_protection(program_config, policy, surcharge_bps, max_buy_per_slot, now)?;
+            }
+            AdminAction::GrantRole { member, role } => {
+                let access_control = ctx.accounts.access_control.as_deref_mut()
+                    .ok_or(ErrorCode::InvalidProposalAccounts)?;
+                admin::grant_role(access_control, member, role, now)?;
+            }
+            AdminAction::RevokeRole { member, role } => {
+                let access_control = ctx.accounts.access_control.as_deref_mut()
+                    .ok_or(ErrorCode::InvalidProposalAccounts)?;
+                admin::revoke_role(program_config, access_control, member, role, now)?;
+            }
+            AdminAction::WithdrawFees { amount, recipient } => {
+                let (Some(fee_vault), Some(recipient_account)) =
+                    (&ctx.accounts.fee_vault, &ctx.accounts.recipient)
+                else {
+                    return Err(ErrorCode::InvalidProposalAccounts.into());
+                };
+                require!(
+                    recipient_account.key() == recipient,
+                    ErrorCode::InvalidProposalAccounts
+                );
+                admin::withdraw_fees(fee_vault.as_ref(), recipient_account, amount)?;
+            }
+            AdminAction::AllowQuoteMint { quote_mint } => {
+                let (Some(quote_mint_account), Some(allowed_quote_mint)) =
+                    (&ctx.accounts.quote_mint, &ctx.accounts.allowed_quote_mint)
+                else {
+                    return Err(ErrorCode::InvalidProposalAccounts.into());
+                };
+                let (address, bump) =
+                    Pubkey::find_program_address(&[b"quote_mint", quote_mint.as_ref()], &crate::ID);
+                require!(
+                    quote_mint_account.key() == quote_mint && allowed_quote_mint.key() == address,
+                    ErrorCode::InvalidProposalAccounts
+                );
+
+                let entry = admin::allow_quote_mint(quote_mint_account, bump)?;
+                let seeds = &[b"quote_mint".as_ref(), quote_mint.as_ref(), &[bump]];
+                trade::create_pda_account(
+                    allowed_quote_mint,
+                    &ctx.accounts.executor.to_account_info(),
+                    8 + AllowedQuoteMint::SIZE,
+                    &[&seeds[..]],
+                    &ctx.accounts.system_program,
+                )?;
+                entry.try_serialize(&mut &mut allowed_quote_mint.try_borrow_mut_data()?[..])?;
+            }
+            AdminAction::DisallowQuoteMint { quote_mint } => {
+                let Some(allowed_quote_mint) = &ctx.accounts.allowed_quote_mint else {
+                    return Err(ErrorCode::InvalidProposalAccounts.into());
+                };
+                let (address, _) =
+                    Pubkey::find_program_address(&[b"quote_mint", quote_mint.as_ref()], &crate::ID);
+                require_keys_eq!(allowed_quote_mint.key(), address, ErrorCode::InvalidProposalAccounts);
+
+                require_keys_eq!(*allowed_quote_mint.owner, crate::ID, ErrorCode::InvalidProposalAccounts);
+
+                admin::disallow_quote_mint(quote_mint)?;
+                trade::close_program_account(
+                    allowed_quote_mint,
+                    &ctx.accounts.executor.to_account_info(),
+                )?;
+            }
+            AdminAction::ProposeAdmin { new_admin } => {
+                admin::propose_admin(program_config, new_admin, now)?;
+            }
+            AdminAction::CancelAdminTransfer => admin::cancel_admin_transfer(program_config, now)?,
+            AdminAction::AcceptAdmin => admin::accept_admin(program_config, multisig.key(), now)?,
+            AdminAction::UpdateMultisig { signers, threshold } => {
+                let eta = now
+                    .checked_add(program_config.timelock_delay)
+                    .ok_or(ErrorCode::Overflow)?;
+                multisig.queue_update(signers.clone(), threshold, eta)?;
+
+                emit!(MultisigUpdateQueuedEvent {
+                    multisig: multisig.key(),
+                    signers,
+                    threshold,
+                    eta,
+                    timestamp: now,
+                });
+            }
+            AdminAction::CancelMultisigUpdate => {
+                let update = multisig.cancel_update()?;
+
+                emit!(MultisigUpdateCancelledEvent {
+                    multisig: multisig.key(),
+                    signers: update.signers,
+                    threshold: update.threshold,
+                    timestamp: now,
+                });
+            }
+        }
+
+        emit!(ProposalExecutedEvent {
+            proposal: proposal.key(),
+            executor: ctx.accounts.executor.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless. Swaps in the signer set queued by an `UpdateMultisig`
+    /// proposal once the timelock delay has passed.
+    pub fn execute_multisig_update(ctx: Context<ExecuteMultisigUpdate>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let now = Clock::get()?.unix_timestamp;
+        multisig.execute_update(now)?;
+
+        emit!(MultisigUpdatedEvent {
+            multisig: multisig.key(),
+            signers: multisig.signers.clone(),
+            threshold: multisig.threshold,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Curve and metadata migrations are permissionless: they only rewrite an
+    // account into the current layout, with the caller paying any extra rent.
+    // The config moves to a new PDA, so only the upgrade authority may run it.
+
+    pub fn migrate_program_config(ctx: Context<MigrateProgramConfig>) -> Result<()> {
+        let legacy: migration::ProgramConfigV0 = migration::read_legacy(
+            &ctx.accounts.legacy_config,
+            &ProgramConfig::DISCRIMINATOR,
+            8 + ProgramConfig::SIZE,
+        )?;
+        ctx.accounts
+            .program_config
+            .set_inner(legacy.upgrade(ctx.bumps.program_config));
+        trade::close_program_account(
+            &ctx.accounts.legacy_config,
+            &ctx.accounts.authority.to_account_info(),
+        )?;
+
+        emit!(AccountMigratedEvent {
+            account: ctx.accounts.program_config.key(),
+            version: CURRENT_ACCOUNT_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Metadata has to be migrated first, since v0 curves take their creator
+    /// from it.
+    /// Also sets up what a v0 curve needs to trade: the creator's profile,
+    /// and mint authority for the curve, which the creator has to sign over.
+    /// Mints whose authority is anyone else can't be migrated.
+    pub fn migrate_bonding_curve(ctx: Context<MigrateBondingCurve>) -> Result<()> {
+        let creator = ctx.accounts.token_metadata.creator;
+        let curve_key = ctx.accounts.bonding_curve.key();
+        let creator_signed = ctx.accounts.creator.as_ref().is_some_and(|signer| signer.key() == creator);
+        let needs_handover = migration::mint_needs_handover(
+            ctx.accounts.token_mint.mint_authority,
+            curve_key,
+            creator,
+            creator_signed,
+        )?;
+
+        migration::migrate_account(
+            &ctx.accounts.bonding_curve,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program,
+            8 + BondingCurveParams::SIZE,
+            |legacy: migration::BondingCurveParamsV0| legacy.upgrade(creator),
+        )?;
+
+        // v0 launches predate creator profiles, so this one was never counted
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.creator = creator;
+        creator_profile.bump = ctx.bumps.creator_profile;
+        creator_profile.tokens_launched = creator_profile.tokens_launched.saturating_add(1);
+
+        if needs_handover {
+            let creator_signer = ctx.accounts.creator.as_ref().ok_or(ErrorCode::LegacyMintAuthority)?;
+            let set_authority_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::SetAuthority {
+                    current_authority: creator_signer.to_account_info(),
+                    account_or_mint: ctx.accounts.token_mint.to_account_info(),
+                },
+            );
+            token::set_authority(set_authority_ctx, AuthorityType::MintTokens, Some(curve_key))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn migrate_token_metadata(ctx: Context<MigrateTokenMetadata>) -> Result<()> {
+        migration::migrate_account(
+            &ctx.accounts.token_metadata,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program,
+            8 + TokenMetadata::SIZE,
+            migration::TokenMetadataV0::upgrade,
+        )
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, symbol: String)]
+pub struct CreateTokenContext<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 9,
+        mint::authority = authority,
+    )]
+    pub token_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenMetadata::SIZE,  // discriminator + metadata size
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_metadata: Account<'info, TokenMetadata>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + BondingCurveParams::SIZE,  // discriminator + params size
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    // Only needed for a dev-buy, so launches without one don't pay its rent
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = token_mint,
+        associated_token::authority = authority,
+    )]
+    pub creator_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + CreatorProfile::SIZE,  // discriminator + profile size
+        seeds = [b"creator", authority.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    /// Receives the creation fee and dev-buy trading fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePoolContext<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = pool_authority,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: This is the PDA that will own the pool
+    #[account(
+        seeds = [b"pool", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct TradeContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        mint::authority = bonding_curve,
+    )]
+    pub token_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", bonding_curve.creator.as_ref()],
+        bump = creator_profile.bump,
+        constraint = creator_profile.creator == bonding_curve.creator @ ErrorCode::Unauthorized,
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserTradeState::SIZE,  // discriminator + user state size
+        seeds = [b"user_state", token_mint.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_trade_state: Account<'info, UserTradeState>,
+
+    /// Receives trading fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+    
+    // The config is a singleton PDA, so the seeds pin the one config every
+    // curve trades under
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TradeContext<'info> {
+    /// A user trade with every check `trade_token` applies. Returns `false`
+    /// when the circuit breaker tripped and halted the market instead.
+    fn trade(
+        &mut self,
+        user_trade_state_bump: u8,
+        amount_in: u64,
+        min_amount_out: u64,
+        is_buy: bool,
+        deadline: Option<TradeDeadline>,
+    ) -> Result<bool> {
+        let program_config = &self.program_config;
+        let clock = Clock::get()?;
+        let slot = clock.slot;
+        trade::require_before_deadline(deadline, &clock)?;
+        trade::require_market_open(program_config, &self.bonding_curve, slot)?;
+        let fee_bps = trade::user_fee_bps(
+            program_config,
+            &self.bonding_curve,
+            &self.user_trade_state,
+            is_buy,
+            &clock,
+        )?;
+
+        let balance_in = if is_buy { self.user.lamports() } else { self.user_token_account.amount };
+        if trade::halt_if_breaker_trips(
+            self.token_mint.key(),
+            &mut self.bonding_curve,
+            &self.program_config,
+            is_buy,
+            amount_in,
+            balance_in,
+            fee_bps,
+            &clock,
+        )? {
+            return Ok(false);
+        }
+
+        let user_trade_state = &mut self.user_trade_state;
+        user_trade_state.user = self.user.key();
+        user_trade_state.token_mint = self.token_mint.key();
+        user_trade_state.bump = user_trade_state_bump;
+        if is_buy {
+            trade::record_user_buy(
+                &self.program_config,
+                &self.bonding_curve,
+                user_trade_state,
+                amount_in,
+                slot,
+            )?;
+        }
+
+        let mut curve_trade = CurveTrade {
+            token_mint: &mut self.token_mint,
+            bonding_curve: &mut self.bonding_curve,
+            creator_profile: &mut self.creator_profile,
+            fee_collector: self.fee_vault.as_ref(),
+            token_program: &self.token_program,
+            system_program: &self.system_program,
+        };
+
+        if is_buy {
+            curve_trade.buy(
+                &self.user.to_account_info(),
+                &self.user_token_account.to_account_info(),
+                amount_in,
+                min_amount_out,
+                fee_bps,
+            )?;
+        } else {
+            curve_trade.sell(
+                &self.user_token_account.to_account_info(),
+                &self.user.to_account_info(),
+                &[],
+                &self.user.to_account_info(),
+                amount_in,
+                min_amount_out,
+                fee_bps,
+            )?;
+        }
+
+        Ok(true)
+    }
+}
+
+impl<'info> QuoteTradeContext<'info> {
+    /// A user trade with every check `trade_token_quote` applies. Returns
+    /// `false` when the circuit breaker tripped and halted the market instead.
+    fn trade(
+        &mut self,
+        user_trade_state_bump: u8,
+        amount_in: u64,
+        min_amount_out: u64,
+        is_buy: bool,
+        deadline: Option<TradeDeadline>,
+    ) -> Result<bool> {
+        let program_config = &self.program_config;
+        let clock = Clock::get()?;
+        let slot = clock.slot;
+        trade::require_before_deadline(deadline, &clock)?;
+        trade::require_market_open(program_config, &self.bonding_curve, slot)?;
+        let fee_bps = trade::user_fee_bps(
+            program_config,
+            &self.bonding_curve,
+            &self.user_trade_state,
+            is_buy,
+            &clock,
+        )?;
+
+        let balance_in = if is_buy { self.user_quote_account.amount } else { self.user_token_account.amount };
+        if trade::halt_if_breaker_trips(
+            self.token_mint.key(),
+            &mut self.bonding_curve,
+            &self.program_config,
+            is_buy,
+            amount_in,
+            balance_in,
+            fee_bps,
+            &clock,
+        )? {
+            return Ok(false);
+        }
+
+        let user_trade_state = &mut self.user_trade_state;
+        user_trade_state.user = self.user.key();
+        user_trade_state.token_mint = self.token_mint.key();
+        user_trade_state.bump = user_trade_state_bump;
+        if is_buy {
+            trade::record_user_buy(
+                &self.program_config,
+                &self.bonding_curve,
+                user_trade_state,
+                amount_in,
+                slot,
+            )?;
+        }
+
+        let quote_accounts = QuoteAccounts {
+            vault: &mut self.quote_vault,
+            trader_account: &self.user_quote_account.to_account_info(),
+        };
+        let mut curve_trade = CurveTrade {
+            token_mint: &mut self.token_mint,
+            bonding_curve: &mut self.bonding_curve,
+            creator_profile: &mut self.creator_profile,
+            fee_collector: &self.fee_collector_quote_account.to_account_info(),
+            token_program: &self.token_program,
+            system_program: &self.system_program,
+        };
+
+        if is_buy {
+            curve_trade.buy_with_quote(
+                quote_accounts,
+                &self.user.to_account_info(),
+                &self.user_token_account.to_account_info(),
+                amount_in,
+                min_amount_out,
+                fee_bps,
+            )?;
+        } else {
+            curve_trade.sell_for_quote(
+                quote_accounts,
+                &self.user_token_account.to_account_info(),
+                &self.user.to_account_info(),
+                amount_in,
+                min_amount_out,
+                fee_bps,
+            )?;
+        }
+
+        Ok(true)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetQuoteMint<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.creator == creator.key() @ ErrorCode::Unauthorized,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    pub quote_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"quote_mint", quote_mint.key().as_ref()],
+        bump = allowed_quote_mint.bump,
+    )]
+    pub allowed_quote_mint: Account<'info, AllowedQuoteMint>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"quote_vault", token_mint.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = bonding_curve,
+    )]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteTradeContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = quote_vault.mint,
+        token::authority = user,
+    )]
+    pub user_quote_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        mint::authority = bonding_curve,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        mut,
+        seeds = [b"quote_vault", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", bonding_curve.creator.as_ref()],
+        bump = creator_profile.bump,
+        constraint = creator_profile.creator == bonding_curve.creator @ ErrorCode::Unauthorized,
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserTradeState::SIZE,  // discriminator + user state size
+        seeds = [b"user_state", token_mint.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_trade_state: Account<'info, UserTradeState>,
+
+    // Fees are paid in the quote mint, to the fee collector's token account
+    #[account(
+        mut,
+        token::mint = quote_vault.mint,
+        token::authority = program_config.fee_collector,
+    )]
+    pub fee_collector_quote_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePresale<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.creator == creator.key() @ ErrorCode::Unauthorized,
+        constraint = !bonding_curve.spl_quote @ ErrorCode::QuoteCurrencyMismatch,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Presale::SIZE,  // discriminator + presale size
+        seeds = [b"presale", token_mint.key().as_ref()],
+        bump
+    )]
+    pub presale: Account<'info, Presale>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PresaleBuy<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        mint::authority = bonding_curve,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        seeds = [b"presale", token_mint.key().as_ref()],
+        bump = presale.bump,
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + PresaleAllocation::SIZE,  // discriminator + allocation size
+        seeds = [b"presale_allocation", token_mint.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub presale_allocation: Account<'info, PresaleAllocation>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", bonding_curve.creator.as_ref()],
+        bump = creator_profile.bump,
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    /// Receives trading fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.creator == creator.key() @ ErrorCode::Unauthorized,
+        constraint = !bonding_curve.spl_quote @ ErrorCode::QuoteCurrencyMismatch,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Auction::SIZE,  // discriminator + auction size
+        seeds = [b"auction", token_mint.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = token_mint,
+        associated_token::authority = auction,
+    )]
+    pub auction_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuctionBidContext<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"auction", token_mint.key().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + AuctionBid::SIZE,  // discriminator + bid size
+        seeds = [b"bid", token_mint.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, AuctionBid>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeAuction<'info> {
+    #[account(
+        mut,
+        mint::authority = bonding_curve,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        mut,
+        seeds = [b"auction", token_mint.key().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = auction,
+    )]
+    pub auction_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", bonding_curve.creator.as_ref()],
+        bump = creator_profile.bump,
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    /// Receives trading fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuctionBid<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    /// CHECK: Gets the auction's rent back after the last settlement
+    #[account(mut, address = bonding_curve.creator @ ErrorCode::Unauthorized)]
+    pub creator: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"auction", token_mint.key().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"bid", token_mint.key().as_ref(), user.key().as_ref()],
+        bump = bid.bump,
+    )]
+    pub bid: Account<'info, AuctionBid>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = auction,
+    )]
+    pub auction_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    // Orders escrow and settle in SOL
+    #[account(
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+        constraint = !bonding_curve.spl_quote @ ErrorCode::QuoteCurrencyMismatch,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LimitOrder::SIZE,  // discriminator + order size
+        seeds = [b"order", token_mint.key().as_ref(), owner.key().as_ref(), order_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    // Token escrow, for sell orders only
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = order,
+    )]
+    pub order_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FillOrder<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    /// CHECK: Order owner, receives the proceeds and the order's rent
+    #[account(
+        mut,
+        address = order.owner,
+    )]
+    pub owner: AccountInfo<'info>,
+
+    // Closed by the handler once filled; a fill that halts the market keeps it
+    #[account(
+        mut,
+        seeds = [b"order", token_mint.key().as_ref(), order.owner.as_ref(), order.order_id.to_le_bytes().as_ref()],
+        bump = order.bump,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    // Required for sell orders. Buy orders have no escrow of their own, but
+    // one created by someone else is swept and closed if passed
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = order,
+    )]
+    pub order_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        mint::authority = bonding_curve,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", bonding_curve.creator.as_ref()],
+        bump = creator_profile.bump,
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    // Buy fills count against the owner's launch and per-slot caps
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        space = 8 + UserTradeState::SIZE,  // discriminator + user state size
+        seeds = [b"user_state", token_mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_trade_state: Account<'info, UserTradeState>,
+
+    /// Receives trading fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"order", token_mint.key().as_ref(), owner.key().as_ref(), order.order_id.to_le_bytes().as_ref()],
+        bump = order.bump,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    // Required for sell orders. Buy orders have no escrow of their own, but
+    // one created by someone else is swept and closed if passed
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = order,
+    )]
+    pub order_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDcaVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    // DCA vaults are funded in SOL
+    #[account(
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+        constraint = !bonding_curve.spl_quote @ ErrorCode::QuoteCurrencyMismatch,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + DcaVault::SIZE,  // discriminator + vault size
+        seeds = [b"dca", token_mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, DcaVault>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDcaVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"dca", vault.token_mint.as_ref(), owner.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, DcaVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDcaVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"dca", vault.token_mint.as_ref(), owner.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, DcaVault>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDca<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"dca", token_mint.key().as_ref(), vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, DcaVault>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault.owner,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        mint::authority = bonding_curve,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", bonding_curve.creator.as_ref()],
+        bump = creator_profile.bump,
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    // DCA buys count against the owner's launch and per-slot caps
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        space = 8 + UserTradeState::SIZE,  // discriminator + user state size
+        seeds = [b"user_state", token_mint.key().as_ref(), vault.owner.as_ref()],
+        bump
+    )]
+    pub owner_trade_state: Account<'info, UserTradeState>,
+
+    /// Receives trading fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchTrade<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Receives trading fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuditCurve<'info> {
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+
+    /// Required for SPL-quoted curves, omitted for SOL curves
+    #[account(
+        seeds = [b"quote_vault", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub quote_vault: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCreatorProfile<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + CreatorProfile::SIZE,  // discriminator + profile size
+        seeds = [b"creator", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(normalized_symbol: String)]
+pub struct ReserveSymbol<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump,
+        constraint = token_metadata.creator == creator.key() @ ErrorCode::Unauthorized
+    )]
+    pub token_metadata: Account<'info, TokenMetadata>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + SymbolRegistry::SIZE,  // discriminator + registry size
+        seeds = [b"symbol", normalized_symbol.as_bytes()],
+        bump
+    )]
+    pub symbol_registry: Account<'info, SymbolRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseSymbol<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"symbol", symbol_registry.symbol.as_bytes()],
+        bump = symbol_registry.bump,
+    )]
+    pub symbol_registry: Account<'info, SymbolRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct SetModerationStatus<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+}
+
+#[derive(Accounts)]
+pub struct ResetCircuitBreaker<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump = bonding_curve.bump,
+    )]
+    pub bonding_curve: Account<'info, BondingCurveParams>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSandwichProtection<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCircuitBreaker<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AllowQuoteMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    pub quote_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AllowedQuoteMint::SIZE,  // discriminator + allowlist entry size
+        seeds = [b"quote_mint", quote_mint.key().as_ref()],
+        bump
+    )]
+    pub allowed_quote_mint: Account<'info, AllowedQuoteMint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisallowQuoteMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"quote_mint", allowed_quote_mint.mint.as_ref()],
+        bump = allowed_quote_mint.bump,
+    )]
+    pub allowed_quote_mint: Account<'info, AllowedQuoteMint>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_mint: Pubkey)]
+pub struct InitializeTreasury<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = treasury_authority,
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    pub token_mint: Account<'info, Mint>,
+    
+    /// CHECK: This is the PDA that will own the treasury
+    #[account(
+        seeds = [b"treasury", token_mint.key().as_ref()],
+        bump
+    )]
+    pub treasury_authority: AccountInfo<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
     
-    /// CHECK: This is the PDA that will own the treasury
     #[account(
-        seeds = [b"treasury", token_mint.key().as_ref()],
-        bump
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+    
+    /// Holds the collected SOL fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + FeeVault::SIZE,  // discriminator + fee vault size
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeProgramConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProgramConfig::SIZE,  // discriminator + config size
+        seeds = [b"config"],
+        bump
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    // The config is a singleton, so only the upgrade authority may create it;
+    // otherwise whoever calls first after deploy would own the protocol
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::PumpFun>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProgramConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAdminTransfer<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct PauseTrading<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+}
+
+#[derive(Accounts)]
+pub struct ResumeTrading<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTradingFee<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCreationFee<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+}
+
+pub const MAX_CREATION_FEE_LAMPORTS: u64 = 1_000_000_000; // 1 SOL
+pub const ADMIN_TRANSFER_WINDOW: i64 = 7 * 24 * 60 * 60; // 7 days to accept
+pub const DEFAULT_TRADING_FEE_BPS: u16 = 100; // 1% = 100 basis points
+pub const KEEPER_FEE_LAMPORTS: u64 = 50_000; // paid to whoever fills a limit order
+pub const MAX_BATCH_LEGS: usize = 5;
+pub const BATCH_LEG_ACCOUNTS: usize = 5; // mint, curve, creator profile, user ATA, user state
+
+#[derive(Accounts)]
+pub struct UpdateTimelockDelay<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConfigChange<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAccessControl<'info> {
+    // Only the admin may create the roles account, so nobody can front-run
+    // it after deploy
+    #[account(
+        mut,
+        address = program_config.admin @ ErrorCode::Unauthorized,
+    )]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessControl::SIZE,  // discriminator + roles size
+        seeds = [b"roles"],
+        bump
+    )]
+    pub access_control: Account<'info, AccessControl>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRoles<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Account<'info, AccessControl>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Multisig::SIZE,  // discriminator + multisig size
+        seeds = [b"multisig"],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::SIZE,  // discriminator + proposal size
+        seeds = [b"proposal", multisig.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    pub authority: Signer<'info>,
+
+    /// CHECK: Receives the proposal's rent; must be its proposer
+    #[account(mut, address = proposal.proposer)]
+    pub proposer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"proposal", proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    // Pays for, and gets the rent back from, allowlist entries
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = program_config.bump,
+    )]
+    pub program_config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.index.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"roles"],
+        bump = access_control.bump,
+    )]
+    pub access_control: Option<Account<'info, AccessControl>>,
+
+    /// Only used by WithdrawFees
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump = fee_vault.bump,
     )]
-    pub treasury_authority: AccountInfo<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    pub fee_vault: Option<Account<'info, FeeVault>>,
 
-#[derive(Accounts)]
-pub struct WithdrawFees<'info> {
+    /// CHECK: Only used by WithdrawFees; checked against the proposal
     #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub program_config: Account<'info, ProgramConfig>,
-    
-    /// CHECK: Account that holds collected fees
+    pub recipient: Option<AccountInfo<'info>>,
+
+    /// Only used by AllowQuoteMint; checked against the proposal
+    pub quote_mint: Option<Account<'info, Mint>>,
+
+    /// CHECK: Only used by AllowQuoteMint and DisallowQuoteMint; the address
+    /// is checked against the proposal's quote mint
     #[account(mut)]
-    pub fee_collector: AccountInfo<'info>,
-    
+    pub allowed_quote_mint: Option<AccountInfo<'info>>,
+
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct InitializeProgramConfig<'info> {
+pub struct ExecuteMultisigUpdate<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProgramConfig<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
+    /// CHECK: The v0 keypair config, which `Account` can't load; owner and
+    /// discriminator are checked by the migration
+    #[account(mut)]
+    pub legacy_config: AccountInfo<'info>,
+
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 32, // discriminator + fee_collector + admin
+        space = 8 + ProgramConfig::SIZE,  // discriminator + config size
+        seeds = [b"config"],
+        bump
     )]
     pub program_config: Account<'info, ProgramConfig>,
 
-    pub system_program: Program<'info, System>,
-}
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::PumpFun>,
 
-#[derive(Accounts)]
-pub struct UpdateProgramConfig<'info> {
-    pub authority: Signer<'info>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
 
-    #[account(mut)]
-    pub program_config: Account<'info, ProgramConfig>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct PauseTrading<'info> {
-    pub authority: Signer<'info>,
+pub struct MigrateBondingCurve<'info> {
     #[account(mut)]
-    pub program_config: Account<'info, ProgramConfig>,
-}
+    pub payer: Signer<'info>,
+
+    /// Only needed while the creator still holds the mint authority
+    pub creator: Option<Signer<'info>>,
 
-#[derive(Accounts)]
-pub struct ResumeTrading<'info> {
-    pub authority: Signer<'info>,
     #[account(mut)]
-    pub program_config: Account<'info, ProgramConfig>,
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub token_metadata: Account<'info, TokenMetadata>,
+
+    /// CHECK: May still be in the old layout, which `Account` can't load;
+    /// owner and discriminator are checked by the migration
+    #[account(
+        mut,
+        seeds = [b"curve", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub bonding_curve: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CreatorProfile::SIZE,  // discriminator + profile size
+        seeds = [b"creator", token_metadata.creator.as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateTradingFee<'info> {
-    pub authority: Signer<'info>,
+pub struct MigrateTokenMetadata<'info> {
     #[account(mut)]
-    pub program_config: Account<'info, ProgramConfig>,
+    pub payer: Signer<'info>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    /// CHECK: May still be in the old layout, which `Account` can't load;
+    /// owner and discriminator are checked by the migration
+    #[account(
+        mut,
+        seeds = [b"metadata", token_mint.key().as_ref()],
+        bump,
+    )]
+    pub token_metadata: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[account]
 pub struct ProgramConfig {
-    pub fee_collector: Pubkey,
+    pub version: u8,
+    pub fee_collector: Pubkey,  // owns the quote-mint fee accounts; SOL fees go to the fee vault
     pub admin: Pubkey,
     pub trading_paused: bool,
     pub trading_fee_bps: u16,
+    pub creation_fee_lamports: u64,
+    pub bump: u8,
+    pub pending_admin: Option<Pubkey>,
+    pub pending_admin_eta: i64,
+    pub pending_admin_expires_at: i64,
+    pub timelock_delay: i64,
+    pub next_change_id: u64,
+    pub pending_changes: Vec<PendingChange>,
+    pub circuit_breaker_bps: u16,          // max price move per window, 0 = disabled
+    pub circuit_breaker_window_slots: u64,
+    pub same_slot_sell_policy: SameSlotSellPolicy,
+    pub same_slot_surcharge_bps: u16,
+    pub max_buy_per_slot: u64,            // lamports per wallet per slot, 0 = no cap
+    pub reserved: [u8; CONFIG_RESERVED_BYTES],
+}
+
+/// Reserved bytes left in `ProgramConfig` after the fields carved out of it
+/// since the v1 layout.
+pub const CONFIG_RESERVED_BYTES: usize = ACCOUNT_RESERVED_BYTES - 11;
+
+impl ProgramConfig {
+    pub const SIZE: usize = 1 + // version
+                           32 + // fee_collector
+                           32 + // admin
+                           1 + // trading_paused
+                           2 + // trading_fee_bps
+                           8 + // creation_fee_lamports
+                           1 + // bump
+                           1 + 32 + // pending_admin
+                           8 + // pending_admin_eta
+                           8 + // pending_admin_expires_at
+                           8 + // timelock_delay
+                           8 + // next_change_id
+                           4 + MAX_PENDING_CHANGES * PendingChange::SIZE + // pending_changes
+                           2 + // circuit_breaker_bps
+                           8 + // circuit_breaker_window_slots
+                           1 + // same_slot_sell_policy
+                           2 + // same_slot_surcharge_bps
+                           8 + // max_buy_per_slot
+                           CONFIG_RESERVED_BYTES; // reserved
+
+    pub fn clear_pending_admin(&mut self) {
+        self.pending_admin = None;
+        self.pending_admin_eta = 0;
+        self.pending_admin_expires_at = 0;
+    }
+
+    pub fn take_pending_change(&mut self, id: u64) -> Result<PendingChange> {
+        let index = self
+            .pending_changes
+            .iter()
+            .position(|pending| pending.id == id)
+            .ok_or(ErrorCode::ChangeNotFound)?;
+        Ok(self.pending_changes.remove(index))
+    }
 }
 
 #[event]
@@ -549,10 +3610,311 @@ pub struct TradeEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct QuoteMintAllowlistEvent {
+    pub quote_mint: Pubkey,
+    pub allowed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct QuoteMintSetEvent {
+    pub token_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub quote_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PresaleCreatedEvent {
+    pub token_mint: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub end_slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionCreatedEvent {
+    pub token_mint: Pubkey,
+    pub end_time: i64,
+    pub max_raise: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionBidEvent {
+    pub token_mint: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_bid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionFinalizedEvent {
+    pub token_mint: Pubkey,
+    pub total_bid: u64,
+    pub filled_sol: u64,
+    pub tokens_sold: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionSettledEvent {
+    pub token_mint: Pubkey,
+    pub user: Pubkey,
+    pub tokens: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderPlacedEvent {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub token_mint: Pubkey,
+    pub side: OrderSide,
+    pub amount: u64,
+    pub limit_price: u64,
+    pub stop_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderFilledEvent {
+    pub order: Pubkey,
+    pub keeper: Pubkey,
+    pub spot_price: u64,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderCancelledEvent {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaVaultCreatedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount_per_period: u64,
+    pub interval_seconds: i64,
+    pub max_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaExecutedEvent {
+    pub vault: Pubkey,
+    pub keeper: Pubkey,
+    pub spot_price: u64,
+    pub amount_in: u64,
+    pub tokens_out: u64,
+    pub next_execution: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CurveAuditEvent {
+    pub token_mint: Pubkey,
+    pub reserve: u64,  // lamports above rent, or quote vault balance for SPL-quoted curves
+    pub required_reserve: u64,
+    pub mint_supply: u64,
+    pub expected_supply: u64,
+    pub solvent: bool,
+    pub supply_matches: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SymbolReservedEvent {
+    pub symbol: String,
+    pub token_mint: Pubkey,
+    pub creator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SymbolReleasedEvent {
+    pub symbol: String,
+    pub token_mint: Pubkey,
+    pub released_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ModerationEvent {
+    pub token_mint: Pubkey,
+    pub status: ModerationStatus,
+    pub reason_code: u16,
+    pub moderator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferProposedEvent {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub eta: i64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferAcceptedEvent {
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferCancelledEvent {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MultisigUpdatedEvent {
+    pub multisig: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MultisigUpdateQueuedEvent {
+    pub multisig: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MultisigUpdateCancelledEvent {
+    pub multisig: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreatedEvent {
+    pub proposal: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalApprovedEvent {
+    pub proposal: Pubkey,
+    pub signer: Pubkey,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCancelledEvent {
+    pub proposal: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecutedEvent {
+    pub proposal: Pubkey,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerTrippedEvent {
+    pub token_mint: Pubkey,
+    pub reference_price: u64,
+    pub attempted_price: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerResetEvent {
+    pub token_mint: Pubkey,
+    pub reset_by: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SandwichProtectionUpdatedEvent {
+    pub policy: SameSlotSellPolicy,
+    pub surcharge_bps: u16,
+    pub max_buy_per_slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerUpdatedEvent {
+    pub threshold_bps: u16,
+    pub window_slots: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleGrantedEvent {
+    pub member: Pubkey,
+    pub role: Role,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleRevokedEvent {
+    pub member: Pubkey,
+    pub role: Role,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeQueuedEvent {
+    pub id: u64,
+    pub change: ConfigChange,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeExecutedEvent {
+    pub id: u64,
+    pub change: ConfigChange,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeCancelledEvent {
+    pub id: u64,
+    pub change: ConfigChange,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct ConfigUpdateEvent {
     pub admin: Pubkey,
     pub fee_collector: Pubkey,
     pub trading_fee_bps: u16,
+    pub creation_fee_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigratedEvent {
+    pub account: Pubkey,
+    pub version: u8,
     pub timestamp: i64,
 }
@@ -0,0 +1,289 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::Discriminator;
+use crate::errors::ErrorCode;
+use crate::state::{
+    BondingCurveParams, LaunchProtection, ModerationStatus, SameSlotSellPolicy,
+    TokenMetadata, ACCOUNT_RESERVED_BYTES, CURRENT_ACCOUNT_VERSION, CURVE_RESERVED_BYTES,
+};
+use crate::trade::transfer_lamports;
+use crate::{AccountMigratedEvent, ProgramConfig, CONFIG_RESERVED_BYTES};
+
+// Upgrades for accounts written before they carried a version byte. The
+// legacy structs below are the layouts of the original release and must never
+// change.
+
+#[derive(AnchorDeserialize)]
+pub struct TokenMetadataV0 {
+    pub name: String,
+    pub symbol: String,
+    pub description: String,
+    pub image_url: String,
+    pub creator: Pubkey,
+    pub creation_time: i64,
+}
+
+impl TokenMetadataV0 {
+    pub fn upgrade(self) -> TokenMetadata {
+        TokenMetadata {
+            version: CURRENT_ACCOUNT_VERSION,
+            name: self.name,
+            symbol: self.symbol,
+            description: self.description,
+            image_url: self.image_url,
+            creator: self.creator,
+            creation_time: self.creation_time,
+            reserved: [0; ACCOUNT_RESERVED_BYTES],
+        }
+    }
+}
+
+#[derive(AnchorDeserialize)]
+pub struct BondingCurveParamsV0 {
+    pub initial_price: u64,
+    pub slope: u64,
+    pub liquidity_target: u64,
+    pub current_supply: u64,
+    pub total_liquidity: u64,
+    pub bump: u8,
+}
+
+impl BondingCurveParamsV0 {
+    /// v0 curves didn't record their creator; it is taken from the token's
+    /// metadata. Everything added since starts out at its default.
+    pub fn upgrade(self, creator: Pubkey) -> BondingCurveParams {
+        BondingCurveParams {
+            version: CURRENT_ACCOUNT_VERSION,
+            initial_price: self.initial_price,
+            slope: self.slope,
+            liquidity_target: self.liquidity_target,
+            current_supply: self.current_supply,
+            total_liquidity: self.total_liquidity,
+            bump: self.bump,
+            creator,
+            moderation_status: ModerationStatus::Active,
+            moderation_reason: 0,
+            reference_price: 0,
+            reference_slot: 0,
+            halted: false,
+            launch_slot: 0,
+            launch_time: 0,
+            launch_protection: LaunchProtection::default(),
+            presale_end_slot: 0,
+            auction_active: false,
+            spl_quote: false,
+            reserved: [0; CURVE_RESERVED_BYTES],
+        }
+    }
+}
+
+/// v0 launches left the creator as the mint authority, so the curve could
+/// never mint. Returns whether the authority still has to be handed to the
+/// curve, which only the creator can sign for; a mint with any other
+/// authority can't be made tradable and is rejected.
+pub fn mint_needs_handover(
+    mint_authority: COption<Pubkey>,
+    curve: Pubkey,
+    creator: Pubkey,
+    creator_signed: bool,
+) -> Result<bool> {
+    if mint_authority == COption::Some(curve) {
+        return Ok(false);
+    }
+    require!(
+        mint_authority == COption::Some(creator) && creator_signed,
+        ErrorCode::LegacyMintAuthority
+    );
+    Ok(true)
+}
+
+/// The v0 config was a keypair account rather than the `[b"config"]` PDA, so
+/// it is copied into a new account instead of being upgraded in place.
+#[derive(AnchorDeserialize)]
+pub struct ProgramConfigV0 {
+    pub fee_collector: Pubkey,
+    pub admin: Pubkey,
+    pub trading_paused: bool,
+    pub trading_fee_bps: u16,
+}
+
+impl ProgramConfigV0 {
+    pub fn upgrade(self, bump: u8) -> ProgramConfig {
+        ProgramConfig {
+            version: CURRENT_ACCOUNT_VERSION,
+            fee_collector: self.fee_collector,
+            admin: self.admin,
+            trading_paused: self.trading_paused,
+            trading_fee_bps: self.trading_fee_bps,
+            creation_fee_lamports: 0,
+            bump,
+            pending_admin: None,
+            pending_admin_eta: 0,
+            pending_admin_expires_at: 0,
+            timelock_delay: 0,
+            next_change_id: 0,
+            pending_changes: Vec::new(),
+            circuit_breaker_bps: 0,
+            circuit_breaker_window_slots: 0,
+            same_slot_sell_policy: SameSlotSellPolicy::Allow,
+            same_slot_surcharge_bps: 0,
+            max_buy_per_slot: 0,
+            reserved: [0; CONFIG_RESERVED_BYTES],
+        }
+    }
+}
+
+/// Reads a program-owned account in its v0 layout. `max_len` excludes
+/// accounts that are already in the current layout.
+pub fn read_legacy<L: AnchorDeserialize>(
+    account: &AccountInfo,
+    discriminator: &[u8],
+    max_len: usize,
+) -> Result<L> {
+    require_keys_eq!(*account.owner, crate::ID, ErrorCode::InvalidAccountVersion);
+
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == *discriminator,
+        ErrorCode::InvalidAccountVersion
+    );
+    require!(data.len() < max_len, ErrorCode::AccountAlreadyMigrated);
+    Ok(L::deserialize(&mut &data[8..]).map_err(|_| ErrorCode::InvalidAccountVersion)?)
+}
+
+/// Rewrites a v0 account in the current layout. v0 accounts are recognised by
+/// their size: they were allocated without the version byte and reserved
+/// space, so they are always smaller than `new_space`. The account is grown to
+/// `new_space` with `payer` covering the extra rent.
+pub fn migrate_account<'info, L, T>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    new_space: usize,
+    upgrade: impl FnOnce(L) -> T,
+) -> Result<()>
+where
+    L: AnchorDeserialize,
+    T: AccountSerialize + Discriminator,
+{
+    let legacy = read_legacy::<L>(account, &T::DISCRIMINATOR, new_space)?;
+
+    // Top up rent for the larger account before growing it
+    let rent_exempt = Rent::get()?.minimum_balance(new_space);
+    let shortfall = rent_exempt.saturating_sub(account.lamports());
+    transfer_lamports(payer, account, system_program, shortfall)?;
+    account.realloc(new_space, true)?;
+
+    let upgraded = upgrade(legacy);
+    let mut data = account.try_borrow_mut_data()?;
+    upgraded.try_serialize(&mut &mut data[..])?;
+
+    emit!(AccountMigratedEvent {
+        account: account.key(),
+        version: CURRENT_ACCOUNT_VERSION,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade::{quote_buy, quote_sell, require_market_open};
+
+    #[test]
+    fn upgrades_baseline_curve() {
+        let creator = Pubkey::new_unique();
+        let mut bytes = Vec::new();
+        for field in [1_000u64, 10, 85_000_000_000, 5_000, 42_000] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.push(254); // bump
+
+        let legacy = BondingCurveParamsV0::deserialize(&mut &bytes[..]).unwrap();
+        let curve = legacy.upgrade(creator);
+
+        assert_eq!(curve.version, CURRENT_ACCOUNT_VERSION);
+        assert_eq!(curve.initial_price, 1_000);
+        assert_eq!(curve.slope, 10);
+        assert_eq!(curve.liquidity_target, 85_000_000_000);
+        assert_eq!(curve.current_supply, 5_000);
+        assert_eq!(curve.total_liquidity, 42_000);
+        assert_eq!(curve.bump, 254);
+        assert_eq!(curve.creator, creator);
+        assert_eq!(curve.moderation_status, ModerationStatus::Active);
+        assert_eq!(curve.launch_protection, LaunchProtection::default());
+        assert!(!curve.halted && !curve.auction_active && !curve.spl_quote);
+    }
+
+    #[test]
+    fn upgrades_baseline_config() {
+        let fee_collector = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(fee_collector.as_ref());
+        bytes.extend_from_slice(admin.as_ref());
+        bytes.push(1); // trading_paused
+        bytes.extend_from_slice(&100u16.to_le_bytes());
+
+        let legacy = ProgramConfigV0::deserialize(&mut &bytes[..]).unwrap();
+        let config = legacy.upgrade(253);
+
+        assert_eq!(config.version, CURRENT_ACCOUNT_VERSION);
+        assert_eq!(config.fee_collector, fee_collector);
+        assert_eq!(config.admin, admin);
+        assert!(config.trading_paused);
+        assert_eq!(config.trading_fee_bps, 100);
+        assert_eq!(config.bump, 253);
+        assert_eq!(config.creation_fee_lamports, 0);
+        assert_eq!(config.pending_admin, None);
+        assert!(config.pending_changes.is_empty());
+        assert_eq!(config.circuit_breaker_bps, 0);
+        assert_eq!(config.same_slot_sell_policy, SameSlotSellPolicy::Allow);
+    }
+
+    #[test]
+    fn legacy_mint_authority_needs_the_creator() {
+        let curve = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let creator_mint = COption::Some(creator);
+
+        assert!(mint_needs_handover(creator_mint, curve, creator, true).unwrap());
+        assert!(!mint_needs_handover(COption::Some(curve), curve, creator, false).unwrap());
+        assert_eq!(
+            mint_needs_handover(creator_mint, curve, creator, false).unwrap_err(),
+            ErrorCode::LegacyMintAuthority.into()
+        );
+        for authority in [COption::None, COption::Some(Pubkey::new_unique())] {
+            assert_eq!(
+                mint_needs_handover(authority, curve, creator, true).unwrap_err(),
+                ErrorCode::LegacyMintAuthority.into()
+            );
+        }
+    }
+
+    #[test]
+    fn migrated_curve_can_trade() {
+        let mut bytes = Vec::new();
+        for field in [1_000_000u64, 100, 17_000_000_000, 1_000_000_000, 1_000_000_000] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.push(254); // bump
+        let curve = BondingCurveParamsV0::deserialize(&mut &bytes[..])
+            .unwrap()
+            .upgrade(Pubkey::new_unique());
+        let config = ProgramConfigV0 {
+            fee_collector: Pubkey::new_unique(),
+            admin: Pubkey::new_unique(),
+            trading_paused: false,
+            trading_fee_bps: 100,
+        }
+        .upgrade(255);
+
+        assert!(require_market_open(&config, &curve, 1).is_ok());
+        let fee_bps = curve.trading_fee_bps(u64::from(config.trading_fee_bps), 1_000);
+        assert!(quote_buy(&curve, 1_000_000, fee_bps).unwrap().tokens_out > 0);
+        assert!(quote_sell(&curve, 1_000_000, fee_bps).unwrap().sol_out > 0);
+    }
+}
@@ -1,42 +1,661 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
 use super::errors::ErrorCode;
+use crate::bonding_curve::BondingCurve;
+use crate::MAX_CREATION_FEE_LAMPORTS;
+
+/// Layout version written by this build. Bump it together with a new
+/// migration in `migration.rs` whenever a versioned account changes shape.
+pub const CURRENT_ACCOUNT_VERSION: u8 = 1;
+
+/// Spare bytes at the end of versioned accounts, so small additions can be
+/// carved out of them without a realloc.
+pub const ACCOUNT_RESERVED_BYTES: usize = 64;
 
 #[account]
 pub struct TokenMetadata {
+    pub version: u8,
     pub name: String,
     pub symbol: String,
     pub description: String,
     pub image_url: String,
     pub creator: Pubkey,
     pub creation_time: i64,
+    pub reserved: [u8; ACCOUNT_RESERVED_BYTES],
 }
 
 #[account]
 pub struct BondingCurveParams {
+    pub version: u8,
     pub initial_price: u64,
     pub slope: u64,
     pub liquidity_target: u64,
     pub current_supply: u64,
     pub total_liquidity: u64,
     pub bump: u8,
+    pub creator: Pubkey,
+    pub moderation_status: ModerationStatus,
+    pub moderation_reason: u16,
+    pub reference_price: u64,  // circuit breaker reference for the current window
+    pub reference_slot: u64,   // slot the current window started
+    pub halted: bool,          // blocks trading until a moderator resets the breaker
+    pub launch_slot: u64,
+    pub launch_time: i64,
+    pub launch_protection: LaunchProtection,
+    pub presale_end_slot: u64, // allowlist-only buying until this slot, 0 = no presale
+    pub auction_active: bool,  // opening batch auction still collecting or unfinalized
+    pub spl_quote: bool,       // reserve held in a `quote_vault` token account, not lamports
+    pub reserved: [u8; CURVE_RESERVED_BYTES],
+}
+
+/// Reserved bytes left in `BondingCurveParams` after the fields carved out of
+/// it since the v1 layout.
+pub const CURVE_RESERVED_BYTES: usize = ACCOUNT_RESERVED_BYTES - 8 - 8 - LaunchProtection::SIZE - 8 - 1 - 1;
+
+/// Anti-sniper settings chosen by the creator at launch. All zero means no
+/// protection.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LaunchProtection {
+    pub trading_delay_slots: u64,  // slots after launch before anyone can trade
+    pub max_buy_slots: u64,        // length of the capped window once trading opens
+    pub max_buy_per_wallet: u64,   // lamports one wallet can spend during that window
+    pub launch_fee_bps: u16,       // fee at launch, decaying to the normal trading fee
+    pub fee_decay_seconds: i64,
+}
+
+impl LaunchProtection {
+    pub const SIZE: usize = 8 + // trading_delay_slots
+                           8 + // max_buy_slots
+                           8 + // max_buy_per_wallet
+                           2 + // launch_fee_bps
+                           8;  // fee_decay_seconds
+
+    pub const MAX_TRADING_DELAY_SLOTS: u64 = 1_500;  // ~10 minutes
+    pub const MAX_FEE_DECAY_SECONDS: i64 = 60 * 60;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.trading_delay_slots <= Self::MAX_TRADING_DELAY_SLOTS
+                && self.launch_fee_bps <= 10_000
+                && (0..=Self::MAX_FEE_DECAY_SECONDS).contains(&self.fee_decay_seconds),
+            ErrorCode::InvalidLaunchProtection
+        );
+        Ok(())
+    }
+}
+
+/// Moderation state set by holders of the moderator role. `Flagged` and
+/// `Hidden` are signals for frontends; `Delisted` also blocks buys on-chain
+/// while still letting holders sell.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ModerationStatus {
+    #[default]
+    Active,
+    Flagged,
+    Hidden,
+    Delisted,
+}
+
+pub const MAX_SYMBOL_LEN: usize = 10;
+
+/// Reservation of a ticker for one token. Keyed by the normalized symbol, so
+/// "Pepe", "PEPE" and " pepe" all map to the same entry.
+#[account]
+pub struct SymbolRegistry {
+    pub symbol: String,
+    pub token_mint: Pubkey,
+    pub creator: Pubkey,
+    pub registered_at: i64,
+    pub bump: u8,
+}
+
+/// Allowlist entry for an SPL mint that new curves may be quoted in, keyed by
+/// the quote mint. Removing it stops new launches but leaves existing curves
+/// trading.
+#[account]
+pub struct AllowedQuoteMint {
+    pub mint: Pubkey,
+    pub bump: u8,
+}
+
+/// Program-owned PDA that collects SOL creation and trading fees. Nobody can
+/// sign for it, so lamports only leave through `withdraw_fees`.
+#[account]
+pub struct FeeVault {
+    pub bump: u8,
+}
+
+pub const MAX_MULTISIG_SIGNERS: usize = 10;
+pub const PROPOSAL_LIFETIME: i64 = 7 * 24 * 60 * 60; // 7 days to collect approvals and execute
+
+/// M-of-N signer set that acts as the protocol admin once created. Admin
+/// actions are wrapped in a `Proposal`, approved by signers and executed once
+/// `threshold` approvals are collected.
+#[account]
+pub struct Multisig {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub proposal_count: u64,
+    pub pending_update: Option<MultisigUpdate>,
+    pub bump: u8,
+}
+
+/// Signer set queued by an `UpdateMultisig` proposal. Like an admin transfer
+/// it only takes effect once the config's timelock delay has passed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MultisigUpdate {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub eta: i64, // earliest time the update can be executed
+}
+
+#[account]
+pub struct Proposal {
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// Serialized admin instruction carried by a multisig proposal.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum AdminAction {
+    UpdateProgramConfig { new_fee_collector: Option<Pubkey> },
+    PauseTrading,
+    ResumeTrading,
+    UpdateTradingFee { new_fee_bps: u16 },
+    UpdateCreationFee { new_fee_lamports: u64 },
+    UpdateTimelockDelay { new_delay: i64 },
+    CancelConfigChange { id: u64 },
+    WithdrawFees { amount: u64, recipient: Pubkey },
+    ProposeAdmin { new_admin: Pubkey },
+    CancelAdminTransfer,
+    UpdateMultisig { signers: Vec<Pubkey>, threshold: u8 },
+    UpdateCircuitBreaker { threshold_bps: u16, window_slots: u64 },
+    UpdateSandwichProtection {
+        policy: SameSlotSellPolicy,
+        surcharge_bps: u16,
+        max_buy_per_slot: u64,
+    },
+    GrantRole { member: Pubkey, role: Role },
+    RevokeRole { member: Pubkey, role: Role },
+    AllowQuoteMint { quote_mint: Pubkey },
+    DisallowQuoteMint { quote_mint: Pubkey },
+    AcceptAdmin,
+    CancelMultisigUpdate,
+}
+
+pub const MAX_ROLE_MEMBERS: usize = 16;
+
+/// Operational roles below the super-admin (`ProgramConfig::admin`), so e.g.
+/// on-call engineers can pause trading without being able to withdraw fees.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Pauser,
+    FeeManager,
+    Moderator,
+    TreasuryWithdrawer,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RoleAssignment {
+    pub member: Pubkey,
+    pub roles: u8, // bitmask of Role::mask()
+}
+
+#[account]
+pub struct AccessControl {
+    pub members: Vec<RoleAssignment>,
+    pub bump: u8,
+}
+
+pub const MAX_PENDING_CHANGES: usize = 4;
+pub const MAX_TIMELOCK_DELAY: i64 = 30 * 24 * 60 * 60; // 30 days
+
+/// Config change that only takes effect after the timelock delay, so traders
+/// get notice before fees or the fee recipient change.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigChange {
+    TradingFee { new_fee_bps: u16 },
+    CreationFee { new_fee_lamports: u64 },
+    FeeCollector { new_fee_collector: Pubkey },
+    TimelockDelay { new_delay: i64 },
+    SandwichProtection {
+        policy: SameSlotSellPolicy,
+        surcharge_bps: u16,
+        max_buy_per_slot: u64,
+    },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PendingChange {
+    pub id: u64,
+    pub change: ConfigChange,
+    pub eta: i64, // earliest time the change can be executed
+}
+
+/// What happens to a sell in the same slot as the seller's last buy on that
+/// market, the pattern of a sandwich around another user's buy.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SameSlotSellPolicy {
+    #[default]
+    Allow,
+    Reject,
+    Surcharge,  // charge `same_slot_surcharge_bps` on top of the trading fee
+}
+
+/// Per-user, per-market trading record used by the sandwich protection.
+#[account]
+pub struct UserTradeState {
+    pub user: Pubkey,
+    pub token_mint: Pubkey,
+    pub last_buy_slot: u64,
+    pub bought_in_slot: u64,  // lamports spent on buys during `last_buy_slot`
+    pub launch_bought: u64,   // lamports spent during the launch protection window
+    pub bump: u8,
+}
+
+impl UserTradeState {
+    pub const SIZE: usize = 32 + // user
+                           32 + // token_mint
+                           8 + // last_buy_slot
+                           8 + // bought_in_slot
+                           8 + // launch_bought
+                           1;  // bump
+
+    pub fn bought_in(&self, slot: u64) -> bool {
+        self.bought_in_slot > 0 && self.last_buy_slot == slot
+    }
+
+    /// Records a buy of `amount` lamports in `slot`. `max_per_slot` caps the
+    /// total a wallet can spend on this market in one slot; 0 means no cap.
+    pub fn record_buy(&mut self, slot: u64, amount: u64, max_per_slot: u64) -> Result<()> {
+        let already_bought = if self.last_buy_slot == slot { self.bought_in_slot } else { 0 };
+        let bought_in_slot = already_bought
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            max_per_slot == 0 || bought_in_slot <= max_per_slot,
+            ErrorCode::SlotBuyLimitExceeded
+        );
+
+        self.last_buy_slot = slot;
+        self.bought_in_slot = bought_in_slot;
+        Ok(())
+    }
+
+    /// Counts a buy against the launch window's per-wallet cap; 0 means no cap.
+    pub fn record_launch_buy(&mut self, amount: u64, max_per_wallet: u64) -> Result<()> {
+        let launch_bought = self
+            .launch_bought
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            max_per_wallet == 0 || launch_bought <= max_per_wallet,
+            ErrorCode::LaunchBuyLimitExceeded
+        );
+
+        self.launch_bought = launch_bought;
+        Ok(())
+    }
+}
+
+pub const MAX_PRESALE_SLOTS: u64 = 216_000;  // ~1 day
+
+/// Allowlist committed by the creator at launch. Leaves are
+/// `keccak(wallet || max_allocation_le)`, and pairs are hashed in sorted
+/// order so proofs don't need direction bits.
+#[account]
+pub struct Presale {
+    pub token_mint: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub bump: u8,
+}
+
+impl Presale {
+    pub const SIZE: usize = 32 + // token_mint
+                           32 + // merkle_root
+                           1;  // bump
+
+    pub fn leaf(wallet: &Pubkey, max_allocation: u64) -> [u8; 32] {
+        keccak::hashv(&[wallet.as_ref(), &max_allocation.to_le_bytes()]).to_bytes()
+    }
+
+    pub fn verify(&self, proof: &[[u8; 32]], leaf: [u8; 32]) -> bool {
+        let computed = proof.iter().fold(leaf, |node, sibling| {
+            if node <= *sibling {
+                keccak::hashv(&[&node, sibling]).to_bytes()
+            } else {
+                keccak::hashv(&[sibling, &node]).to_bytes()
+            }
+        });
+        computed == self.merkle_root
+    }
+}
+
+/// How much of its presale allocation a wallet has spent.
+#[account]
+pub struct PresaleAllocation {
+    pub user: Pubkey,
+    pub token_mint: Pubkey,
+    pub spent: u64,
+    pub bump: u8,
+}
+
+impl PresaleAllocation {
+    pub const SIZE: usize = 32 + // user
+                           32 + // token_mint
+                           8 + // spent
+                           1;  // bump
+
+    pub fn record_spend(&mut self, amount: u64, max_allocation: u64) -> Result<()> {
+        let spent = self.spent.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        require!(spent <= max_allocation, ErrorCode::PresaleAllocationExceeded);
+        self.spent = spent;
+        Ok(())
+    }
+}
+
+pub const MAX_AUCTION_SECONDS: i64 = 60 * 60;
+
+/// Opening batch auction. Bids are held in this PDA until `end_time`; then
+/// `finalize_auction` buys `filled_sol` from the curve in one go, so every
+/// bidder pays the same clearing price. When bids exceed `max_raise` each
+/// bid is filled pro rata and the rest is refunded on settlement.
+#[account]
+pub struct Auction {
+    pub token_mint: Pubkey,
+    pub end_time: i64,
+    pub max_raise: u64,    // lamports, 0 = no cap
+    pub total_bid: u64,
+    pub filled_sol: u64,
+    pub tokens_sold: u64,  // held in the auction's token account until settled
+    pub settled_bid: u64,  // lamports of bids settled so far
+    pub finalized: bool,
+    pub bump: u8,
+}
+
+impl Auction {
+    pub const SIZE: usize = 32 + // token_mint
+                           8 + // end_time
+                           8 + // max_raise
+                           8 + // total_bid
+                           8 + // filled_sol
+                           8 + // tokens_sold
+                           8 + // settled_bid
+                           1 + // finalized
+                           1;  // bump
+
+    /// Lamports of the collected bids that the auction will spend on the curve.
+    pub fn fill_amount(&self) -> u64 {
+        if self.max_raise == 0 {
+            self.total_bid
+        } else {
+            self.total_bid.min(self.max_raise)
+        }
+    }
+
+    /// Tokens and refund owed for a bid of `amount` once finalized. Both round
+    /// down, so settling every bid never pays out more than the auction holds.
+    pub fn allocation(&self, amount: u64) -> Result<(u64, u64)> {
+        if self.total_bid == 0 || self.filled_sol == 0 {
+            return Ok((0, amount));
+        }
+
+        let total_bid = u128::from(self.total_bid);
+        let tokens = u128::from(amount) * u128::from(self.tokens_sold) / total_bid;
+        let filled = (u128::from(amount) * u128::from(self.filled_sol)).div_ceil(total_bid);
+        let tokens = u64::try_from(tokens).map_err(|_| error!(ErrorCode::Overflow))?;
+        let filled = u64::try_from(filled).map_err(|_| error!(ErrorCode::Overflow))?;
+        Ok((tokens, amount - filled))
+    }
+
+    /// Counts a bid of `amount` as settled and returns whether it was the last.
+    pub fn record_settlement(&mut self, amount: u64) -> Result<bool> {
+        self.settled_bid = self.settled_bid
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(self.settled_bid == self.total_bid)
+    }
+}
+
+#[account]
+pub struct AuctionBid {
+    pub user: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl AuctionBid {
+    pub const SIZE: usize = 32 + // user
+                           32 + // token_mint
+                           8 + // amount
+                           1;  // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderSide {
+    Buy,   // escrows SOL, fills when the spot price is at or below the limit
+    Sell,  // escrows tokens, fills at or above the limit (take-profit) or at or below the stop
+}
+
+/// Resting limit or trigger order against a curve. The escrow (SOL in this
+/// account for buys, tokens in its associated token account for sells) is
+/// released by `fill_order` or returned by `cancel_order`.
+///
+/// A sell with a `stop_price` is a stop-loss; with both prices set it's a
+/// bracket that sells on whichever level is reached first.
+#[account]
+pub struct LimitOrder {
+    pub owner: Pubkey,
+    pub token_mint: Pubkey,
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub amount: u64,       // lamports for buys, tokens for sells
+    pub limit_price: u64,  // in the units of `BondingCurve::calculate_price`, 0 = none
+    pub stop_price: u64,   // sells only, 0 = none
+    pub min_amount_out: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    pub const SIZE: usize = 32 + // owner
+                           32 + // token_mint
+                           8 + // order_id
+                           1 + // side
+                           8 + // amount
+                           8 + // limit_price
+                           8 + // stop_price
+                           8 + // min_amount_out
+                           8 + // created_at
+                           1;  // bump
+
+    pub fn validate_prices(side: OrderSide, limit_price: u64, stop_price: u64) -> Result<()> {
+        let valid = match side {
+            OrderSide::Buy => limit_price > 0 && stop_price == 0,
+            OrderSide::Sell => {
+                (limit_price > 0 || stop_price > 0)
+                    && (limit_price == 0 || stop_price < limit_price)
+            }
+        };
+        require!(valid, ErrorCode::InvalidOrderPrices);
+        Ok(())
+    }
+
+    pub fn is_triggered(&self, spot_price: u64) -> bool {
+        match self.side {
+            OrderSide::Buy => spot_price <= self.limit_price,
+            OrderSide::Sell => {
+                (self.limit_price > 0 && spot_price >= self.limit_price)
+                    || (self.stop_price > 0 && spot_price <= self.stop_price)
+            }
+        }
+    }
+}
+
+pub const MIN_DCA_INTERVAL_SECONDS: i64 = 60;
+
+/// Recurring buy vault. Holds `balance` lamports for the owner and buys
+/// `amount_per_period` of `token_mint` every `interval_seconds` through
+/// `execute_dca`, as long as the spot price is at most `max_price`.
+#[account]
+pub struct DcaVault {
+    pub owner: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount_per_period: u64,
+    pub interval_seconds: i64,
+    pub max_price: u64,        // skip periods above this spot price, 0 = no limit
+    pub next_execution: i64,
+    pub balance: u64,          // deposited lamports not yet spent
+    pub paused: bool,
+    pub bump: u8,
+}
+
+impl DcaVault {
+    pub const SIZE: usize = 32 + // owner
+                           32 + // token_mint
+                           8 + // amount_per_period
+                           8 + // interval_seconds
+                           8 + // max_price
+                           8 + // next_execution
+                           8 + // balance
+                           1 + // paused
+                           1;  // bump
+
+    /// Checks that the vault can run a period at `now` at `spot_price`, and
+    /// returns the lamports it takes out of the balance (the buy plus the
+    /// keeper fee).
+    pub fn check_due(&self, now: i64, spot_price: u64, keeper_fee: u64) -> Result<u64> {
+        require!(!self.paused, ErrorCode::DcaPaused);
+        require!(now >= self.next_execution, ErrorCode::DcaNotDue);
+        require!(
+            self.max_price == 0 || spot_price <= self.max_price,
+            ErrorCode::DcaPriceAboveLimit
+        );
+
+        let cost = self
+            .amount_per_period
+            .checked_add(keeper_fee)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(self.balance >= cost, ErrorCode::DcaInsufficientBalance);
+        Ok(cost)
+    }
+}
+
+/// One trade in a `batch_trade`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct TradeLeg {
+    pub token_mint: Pubkey,
+    pub is_buy: bool,
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+}
+
+/// Latest point a user trade may still execute, by wall clock or by slot.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeDeadline {
+    UnixTimestamp(i64),
+    Slot(u64),
+}
+
+impl TradeDeadline {
+    /// A trade executing exactly at the deadline is still in time.
+    pub fn has_passed(&self, unix_timestamp: i64, slot: u64) -> bool {
+        match *self {
+            TradeDeadline::UnixTimestamp(deadline) => unix_timestamp > deadline,
+            TradeDeadline::Slot(deadline) => slot > deadline,
+        }
+    }
+}
+
+pub const MAX_DISPLAY_NAME_LEN: usize = 32;
+pub const MAX_AVATAR_URI_LEN: usize = 200;
+
+#[account]
+pub struct CreatorProfile {
+    pub creator: Pubkey,
+    pub display_name: String,
+    pub avatar_uri: String,
+    pub tokens_launched: u64,
+    pub tokens_graduated: u64,
+    pub total_volume: u64,  // SOL traded across all of the creator's tokens
+    pub protocol_fees_generated: u64, // protocol fees paid on trades of the creator's tokens
+    pub bump: u8,
 }
 
 impl TokenMetadata {
-    pub const SIZE: usize = 32 + // name
-                           10 + // symbol
-                           200 + // description
-                           200 + // image_url
+    pub const SIZE: usize = 1 + // version
+                           4 + 32 + // name
+                           4 + 10 + // symbol
+                           4 + 200 + // description
+                           4 + 200 + // image_url
                            32 + // creator
-                           8;   // creation_time
+                           8 + // creation_time
+                           ACCOUNT_RESERVED_BYTES; // reserved
 }
 
 impl BondingCurveParams {
-    pub const SIZE: usize = 8 + // initial_price
+    pub const SIZE: usize = 1 + // version
+                           8 + // initial_price
                            8 + // slope
                            8 + // liquidity_target
                            8 + // current_supply
                            8 + // total_liquidity
-                           1;  // bump
+                           1 + // bump
+                           32 + // creator
+                           1 + // moderation_status
+                           2 + // moderation_reason
+                           8 + // reference_price
+                           8 + // reference_slot
+                           1 + // halted
+                           8 + // launch_slot
+                           8 + // launch_time
+                           LaunchProtection::SIZE + // launch_protection
+                           8 + // presale_end_slot
+                           1 + // auction_active
+                           1 + // spl_quote
+                           CURVE_RESERVED_BYTES; // reserved
+
+    pub fn trading_open(&self, slot: u64) -> bool {
+        slot >= self.launch_slot.saturating_add(self.launch_protection.trading_delay_slots)
+            && slot >= self.presale_end_slot
+            && !self.auction_active
+    }
+
+    /// Whether `slot` falls in the capped-buy window right after trading opens.
+    pub fn in_launch_window(&self, slot: u64) -> bool {
+        let opens = self.launch_slot.saturating_add(self.launch_protection.trading_delay_slots);
+        slot < opens.saturating_add(self.launch_protection.max_buy_slots)
+    }
+
+    /// Trading fee at `now`: the launch fee decays linearly to `base_fee_bps`
+    /// over `fee_decay_seconds`, and never goes below the base fee.
+    pub fn trading_fee_bps(&self, base_fee_bps: u64, now: i64) -> u64 {
+        let launch_fee_bps = u64::from(self.launch_protection.launch_fee_bps);
+        let decay = self.launch_protection.fee_decay_seconds;
+        let elapsed = now.saturating_sub(self.launch_time).max(0);
+        if launch_fee_bps <= base_fee_bps || elapsed >= decay {
+            return base_fee_bps;
+        }
+
+        let remaining = (decay - elapsed) as u64;
+        base_fee_bps + (launch_fee_bps - base_fee_bps) * remaining / decay as u64
+    }
+
+    /// Reopens a halted market and starts a new breaker window at the current
+    /// price, which is returned.
+    pub fn reset_circuit_breaker(&mut self, slot: u64) -> Result<u64> {
+        let price = BondingCurve::calculate_price(self.total_liquidity)?;
+        self.halted = false;
+        self.reference_price = price;
+        self.reference_slot = slot;
+        Ok(price)
+    }
 
     pub fn calculate_buy_return(&self, sol_amount: u64) -> Result<u64> {
         // Calculate the price for the current supply
@@ -87,4 +706,619 @@ impl BondingCurveParams {
 
         Ok(price)
     }
-} 
\ No newline at end of file
+}
+
+impl SymbolRegistry {
+    pub const SIZE: usize = 4 + MAX_SYMBOL_LEN + // symbol
+                           32 + // token_mint
+                           32 + // creator
+                           8 + // registered_at
+                           1;  // bump
+
+    /// Case-insensitive registry key for a symbol. Only ASCII letters and
+    /// digits are accepted, so look-alike unicode tickers can't be reserved.
+    pub fn normalize(symbol: &str) -> Result<String> {
+        let normalized = symbol.trim().to_ascii_uppercase();
+        require!(
+            !normalized.is_empty()
+                && normalized.len() <= MAX_SYMBOL_LEN
+                && normalized.bytes().all(|b| b.is_ascii_alphanumeric()),
+            ErrorCode::InvalidSymbol
+        );
+        Ok(normalized)
+    }
+}
+
+impl FeeVault {
+    pub const SIZE: usize = 1; // bump
+}
+
+impl AllowedQuoteMint {
+    pub const SIZE: usize = 32 + // mint
+                           1;  // bump
+}
+
+impl Multisig {
+    pub const SIZE: usize = 4 + 32 * MAX_MULTISIG_SIGNERS + // signers
+                           1 + // threshold
+                           8 + // proposal_count
+                           1 + MultisigUpdate::SIZE + // pending_update
+                           1;  // bump
+
+    pub fn validate(signers: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !signers.is_empty() && signers.len() <= MAX_MULTISIG_SIGNERS,
+            ErrorCode::InvalidMultisig
+        );
+        require!(
+            threshold > 0 && threshold as usize <= signers.len(),
+            ErrorCode::InvalidMultisig
+        );
+        for (i, signer) in signers.iter().enumerate() {
+            require!(!signers[..i].contains(signer), ErrorCode::InvalidMultisig);
+        }
+        Ok(())
+    }
+
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+
+    /// Approvals that still count, i.e. from keys that are current signers.
+    pub fn count_approvals(&self, proposal: &Proposal) -> usize {
+        proposal.approvals.iter().filter(|key| self.is_signer(key)).count()
+    }
+
+    /// Records `signer`'s approval. Approvals from keys rotated out of the
+    /// signer set are dropped first, so the list never outgrows the set.
+    pub fn approve(&self, proposal: &mut Proposal, signer: Pubkey) -> Result<()> {
+        require!(self.is_signer(&signer), ErrorCode::NotMultisigSigner);
+        require!(!proposal.approvals.contains(&signer), ErrorCode::AlreadyApproved);
+
+        proposal.approvals.retain(|key| self.is_signer(key));
+        proposal.approvals.push(signer);
+        Ok(())
+    }
+
+    /// Queues a new signer set, replacing any update already queued.
+    pub fn queue_update(&mut self, signers: Vec<Pubkey>, threshold: u8, eta: i64) -> Result<()> {
+        Multisig::validate(&signers, threshold)?;
+        self.pending_update = Some(MultisigUpdate { signers, threshold, eta });
+        Ok(())
+    }
+
+    /// Swaps in the queued signer set once its eta has passed.
+    pub fn execute_update(&mut self, now: i64) -> Result<()> {
+        let update = self.pending_update.as_ref().ok_or(ErrorCode::NoPendingMultisigUpdate)?;
+        require!(now >= update.eta, ErrorCode::TimelockNotElapsed);
+
+        let update = self.pending_update.take().ok_or(ErrorCode::NoPendingMultisigUpdate)?;
+        self.signers = update.signers;
+        self.threshold = update.threshold;
+        Ok(())
+    }
+
+    pub fn cancel_update(&mut self) -> Result<MultisigUpdate> {
+        self.pending_update.take().ok_or(error!(ErrorCode::NoPendingMultisigUpdate))
+    }
+}
+
+impl MultisigUpdate {
+    pub const SIZE: usize = 4 + 32 * MAX_MULTISIG_SIGNERS + // signers
+                           1 + // threshold
+                           8;  // eta
+}
+
+impl Proposal {
+    pub const SIZE: usize = 8 + // index
+                           32 + // proposer
+                           AdminAction::SIZE + // action
+                           4 + 32 * MAX_MULTISIG_SIGNERS + // approvals
+                           1 + // executed
+                           8 + // created_at
+                           1;  // bump
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.created_at.saturating_add(PROPOSAL_LIFETIME)
+    }
+}
+
+impl AdminAction {
+    // Largest variant is UpdateMultisig: tag + signers vec + threshold
+    pub const SIZE: usize = 1 + 4 + 32 * MAX_MULTISIG_SIGNERS + 1;
+}
+
+impl Role {
+    pub fn mask(self) -> u8 {
+        1 << (self as u8)
+    }
+}
+
+impl RoleAssignment {
+    pub const SIZE: usize = 32 + // member
+                           1;  // roles
+}
+
+impl AccessControl {
+    pub const SIZE: usize = 4 + MAX_ROLE_MEMBERS * RoleAssignment::SIZE + // members
+                           1;  // bump
+
+    pub fn has_role(&self, member: &Pubkey, role: Role) -> bool {
+        self.members
+            .iter()
+            .any(|assignment| assignment.member == *member && assignment.roles & role.mask() != 0)
+    }
+}
+
+impl ConfigChange {
+    // Largest variant is FeeCollector: tag + pubkey
+    pub const SIZE: usize = 1 + 32;
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            ConfigChange::TradingFee { new_fee_bps } => {
+                require!(*new_fee_bps <= 1000, ErrorCode::InvalidFeePercentage); // Max 10%
+            }
+            ConfigChange::CreationFee { new_fee_lamports } => {
+                require!(
+                    *new_fee_lamports <= MAX_CREATION_FEE_LAMPORTS,
+                    ErrorCode::InvalidCreationFee
+                );
+            }
+            ConfigChange::FeeCollector { new_fee_collector } => {
+                require!(
+                    *new_fee_collector != Pubkey::default(),
+                    ErrorCode::InvalidFeeCollector
+                );
+            }
+            ConfigChange::TimelockDelay { new_delay } => {
+                require!(
+                    (0..=MAX_TIMELOCK_DELAY).contains(new_delay),
+                    ErrorCode::InvalidTimelockDelay
+                );
+            }
+            ConfigChange::SandwichProtection { surcharge_bps, .. } => {
+                require!(*surcharge_bps <= 10_000, ErrorCode::InvalidSandwichProtection);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PendingChange {
+    pub const SIZE: usize = 8 + // id
+                           ConfigChange::SIZE + // change
+                           8;  // eta
+}
+
+impl CreatorProfile {
+    pub const SIZE: usize = 32 + // creator
+                           4 + MAX_DISPLAY_NAME_LEN + // display_name
+                           4 + MAX_AVATAR_URI_LEN + // avatar_uri
+                           8 + // tokens_launched
+                           8 + // tokens_graduated
+                           8 + // total_volume
+                           8 + // protocol_fees_generated
+                           1;  // bump
+
+    pub fn record_trade(&mut self, volume: u64, fee: u64) {
+        // Stats must never block a trade, so they saturate instead of failing
+        self.total_volume = self.total_volume.saturating_add(volume);
+        self.protocol_fees_generated = self.protocol_fees_generated.saturating_add(fee);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_state() -> UserTradeState {
+        UserTradeState {
+            user: Pubkey::default(),
+            token_mint: Pubkey::default(),
+            last_buy_slot: 0,
+            bought_in_slot: 0,
+            launch_bought: 0,
+            bump: 255,
+        }
+    }
+
+    fn protected_curve() -> BondingCurveParams {
+        BondingCurveParams {
+            version: CURRENT_ACCOUNT_VERSION,
+            initial_price: 1_000_000,
+            slope: 100,
+            liquidity_target: 17_000_000_000,
+            current_supply: 0,
+            total_liquidity: 0,
+            bump: 255,
+            creator: Pubkey::default(),
+            moderation_status: ModerationStatus::Active,
+            moderation_reason: 0,
+            reference_price: 0,
+            reference_slot: 0,
+            halted: false,
+            launch_slot: 100,
+            launch_time: 1_000,
+            launch_protection: LaunchProtection {
+                trading_delay_slots: 10,
+                max_buy_slots: 20,
+                max_buy_per_wallet: 1_000,
+                launch_fee_bps: 2_100,
+                fee_decay_seconds: 100,
+            },
+            presale_end_slot: 0,
+            auction_active: false,
+            spl_quote: false,
+            reserved: [0; CURVE_RESERVED_BYTES],
+        }
+    }
+
+    #[test]
+    fn approvals_are_pruned_to_current_signers() {
+        let signers: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let rotated_out = Pubkey::new_unique();
+        let multisig = Multisig {
+            signers: signers.clone(),
+            threshold: 2,
+            proposal_count: 1,
+            pending_update: None,
+            bump: 255,
+        };
+        let mut proposal = Proposal {
+            index: 0,
+            proposer: rotated_out,
+            action: AdminAction::PauseTrading,
+            approvals: vec![rotated_out, signers[0]],
+            executed: false,
+            created_at: 1_000,
+            bump: 255,
+        };
+
+        multisig.approve(&mut proposal, signers[1]).unwrap();
+        assert_eq!(proposal.approvals, vec![signers[0], signers[1]]);
+        assert!(multisig.approve(&mut proposal, signers[1]).is_err());
+        assert!(multisig.approve(&mut proposal, rotated_out).is_err());
+
+        assert!(!proposal.is_expired(1_000 + PROPOSAL_LIFETIME - 1));
+        assert!(proposal.is_expired(1_000 + PROPOSAL_LIFETIME));
+    }
+
+    #[test]
+    fn multisig_update_waits_for_its_eta() {
+        let signers: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let new_signers: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+        let mut multisig = Multisig {
+            signers: signers.clone(),
+            threshold: 2,
+            proposal_count: 0,
+            pending_update: None,
+            bump: 255,
+        };
+
+        assert_eq!(
+            multisig.queue_update(new_signers.clone(), 3, 1_100).unwrap_err(),
+            ErrorCode::InvalidMultisig.into()
+        );
+        multisig.queue_update(new_signers.clone(), 1, 1_100).unwrap();
+        assert_eq!(
+            multisig.execute_update(1_099).unwrap_err(),
+            ErrorCode::TimelockNotElapsed.into()
+        );
+        assert_eq!(multisig.signers, signers);
+
+        multisig.execute_update(1_100).unwrap();
+        assert_eq!(multisig.signers, new_signers);
+        assert_eq!(multisig.threshold, 1);
+        assert_eq!(
+            multisig.execute_update(1_100).unwrap_err(),
+            ErrorCode::NoPendingMultisigUpdate.into()
+        );
+    }
+
+    #[test]
+    fn cancelled_multisig_update_never_applies() {
+        let signers: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut multisig = Multisig {
+            signers: signers.clone(),
+            threshold: 2,
+            proposal_count: 0,
+            pending_update: None,
+            bump: 255,
+        };
+
+        multisig.queue_update(vec![Pubkey::new_unique()], 1, 1_100).unwrap();
+        assert_eq!(multisig.cancel_update().unwrap().threshold, 1);
+        assert_eq!(
+            multisig.execute_update(2_000).unwrap_err(),
+            ErrorCode::NoPendingMultisigUpdate.into()
+        );
+        assert_eq!(
+            multisig.cancel_update().unwrap_err(),
+            ErrorCode::NoPendingMultisigUpdate.into()
+        );
+        assert_eq!(multisig.signers, signers);
+    }
+
+    #[test]
+    fn symbols_normalize_case_insensitively() {
+        for symbol in ["PePe", "PEPE", " pepe "] {
+            assert_eq!(SymbolRegistry::normalize(symbol).unwrap(), "PEPE");
+        }
+    }
+
+    #[test]
+    fn invalid_symbols_are_rejected() {
+        for symbol in ["", "   ", "ABCDEFGHIJK", "PE PE", "PEPÉ"] {
+            assert_eq!(
+                SymbolRegistry::normalize(symbol).unwrap_err(),
+                ErrorCode::InvalidSymbol.into()
+            );
+        }
+        assert!(SymbolRegistry::normalize("ABCDEFGHIJ").is_ok());
+    }
+
+    #[test]
+    fn deadline_passes_after_its_time_or_slot() {
+        let by_time = TradeDeadline::UnixTimestamp(1_000);
+        assert!(!by_time.has_passed(1_000, u64::MAX));
+        assert!(by_time.has_passed(1_001, 0));
+
+        let by_slot = TradeDeadline::Slot(500);
+        assert!(!by_slot.has_passed(i64::MAX, 500));
+        assert!(by_slot.has_passed(0, 501));
+    }
+
+    #[test]
+    fn new_curve_fields_fit_in_reserved_space() {
+        // v1 layout: 94 bytes of fields plus the reserved tail
+        assert_eq!(BondingCurveParams::SIZE, 94 + ACCOUNT_RESERVED_BYTES);
+    }
+
+    #[test]
+    fn trading_opens_after_delay() {
+        let curve = protected_curve();
+        assert!(!curve.trading_open(109));
+        assert!(curve.trading_open(110));
+    }
+
+    #[test]
+    fn launch_window_follows_opening() {
+        let curve = protected_curve();
+        assert!(curve.in_launch_window(129));
+        assert!(!curve.in_launch_window(130));
+    }
+
+    #[test]
+    fn launch_fee_decays_to_base_fee() {
+        let curve = protected_curve();
+        assert_eq!(curve.trading_fee_bps(100, 1_000), 2_100);
+        assert_eq!(curve.trading_fee_bps(100, 1_050), 1_100);
+        assert_eq!(curve.trading_fee_bps(100, 1_100), 100);
+        assert_eq!(curve.trading_fee_bps(3_000, 1_000), 3_000);
+    }
+
+    #[test]
+    fn presale_keeps_trading_closed() {
+        let mut curve = protected_curve();
+        curve.presale_end_slot = 200;
+        assert!(!curve.trading_open(199));
+        assert!(curve.trading_open(200));
+    }
+
+    fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        keccak::hashv(&[&first, &second]).to_bytes()
+    }
+
+    #[test]
+    fn presale_proof_verifies() {
+        let wallets = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves: Vec<[u8; 32]> = wallets.iter().map(|w| Presale::leaf(w, 1_000)).collect();
+        let left = hash_pair(leaves[0], leaves[1]);
+        let presale = Presale {
+            token_mint: Pubkey::default(),
+            merkle_root: hash_pair(left, leaves[2]),
+            bump: 255,
+        };
+
+        assert!(presale.verify(&[leaves[1], leaves[2]], leaves[0]));
+        assert!(presale.verify(&[left], leaves[2]));
+        // Wrong allocation or a wallet outside the tree
+        assert!(!presale.verify(&[leaves[1], leaves[2]], Presale::leaf(&wallets[0], 2_000)));
+        assert!(!presale.verify(&[left], Presale::leaf(&Pubkey::new_unique(), 1_000)));
+    }
+
+    #[test]
+    fn presale_spend_over_allocation_is_rejected() {
+        let mut allocation = PresaleAllocation {
+            user: Pubkey::default(),
+            token_mint: Pubkey::default(),
+            spent: 0,
+            bump: 255,
+        };
+        allocation.record_spend(700, 1_000).unwrap();
+        assert_eq!(
+            allocation.record_spend(301, 1_000).unwrap_err(),
+            ErrorCode::PresaleAllocationExceeded.into()
+        );
+    }
+
+    fn auction(max_raise: u64, total_bid: u64, tokens_sold: u64) -> Auction {
+        let mut auction = Auction {
+            token_mint: Pubkey::default(),
+            end_time: 0,
+            max_raise,
+            total_bid,
+            filled_sol: 0,
+            tokens_sold,
+            settled_bid: 0,
+            finalized: true,
+            bump: 255,
+        };
+        auction.filled_sol = auction.fill_amount();
+        auction
+    }
+
+    #[test]
+    fn undersubscribed_auction_fills_every_bid() {
+        let auction = auction(0, 1_000, 5_000);
+        assert_eq!(auction.allocation(200).unwrap(), (1_000, 0));
+    }
+
+    #[test]
+    fn oversubscribed_auction_fills_pro_rata() {
+        let auction = auction(500, 1_000, 5_000);
+        assert_eq!(auction.allocation(200).unwrap(), (1_000, 100));
+    }
+
+    #[test]
+    fn auction_rounding_never_overpays() {
+        let auction = auction(2, 3, 10);
+        let (tokens, refund) = auction.allocation(1).unwrap();
+        assert_eq!((tokens, refund), (3, 0));
+        assert!(3 * tokens <= auction.tokens_sold);
+    }
+
+    #[test]
+    fn last_settlement_is_detected() {
+        let mut auction = auction(0, 300, 3_000);
+        assert!(!auction.record_settlement(100).unwrap());
+        assert!(!auction.record_settlement(150).unwrap());
+        assert!(auction.record_settlement(50).unwrap());
+    }
+
+    #[test]
+    fn empty_auction_refunds_everything() {
+        let auction = auction(500, 0, 0);
+        assert_eq!(auction.allocation(0).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn limit_orders_trigger_when_price_crosses() {
+        let mut order = LimitOrder {
+            owner: Pubkey::default(),
+            token_mint: Pubkey::default(),
+            order_id: 0,
+            side: OrderSide::Buy,
+            amount: 1_000,
+            limit_price: 500,
+            stop_price: 0,
+            min_amount_out: 0,
+            created_at: 0,
+            bump: 255,
+        };
+        assert!(order.is_triggered(500));
+        assert!(!order.is_triggered(501));
+
+        order.side = OrderSide::Sell;
+        assert!(order.is_triggered(500));
+        assert!(!order.is_triggered(499));
+    }
+
+    #[test]
+    fn stop_loss_triggers_below_stop() {
+        let mut order = LimitOrder {
+            owner: Pubkey::default(),
+            token_mint: Pubkey::default(),
+            order_id: 0,
+            side: OrderSide::Sell,
+            amount: 1_000,
+            limit_price: 0,
+            stop_price: 300,
+            min_amount_out: 0,
+            created_at: 0,
+            bump: 255,
+        };
+        assert!(order.is_triggered(300));
+        assert!(!order.is_triggered(301));
+
+        // Bracket: sells on either side of the range
+        order.limit_price = 500;
+        assert!(order.is_triggered(500));
+        assert!(order.is_triggered(200));
+        assert!(!order.is_triggered(400));
+    }
+
+    #[test]
+    fn order_prices_are_validated() {
+        assert!(LimitOrder::validate_prices(OrderSide::Buy, 500, 0).is_ok());
+        assert!(LimitOrder::validate_prices(OrderSide::Buy, 500, 300).is_err());
+        assert!(LimitOrder::validate_prices(OrderSide::Sell, 0, 300).is_ok());
+        assert!(LimitOrder::validate_prices(OrderSide::Sell, 500, 300).is_ok());
+        assert!(LimitOrder::validate_prices(OrderSide::Sell, 300, 500).is_err());
+        assert!(LimitOrder::validate_prices(OrderSide::Sell, 0, 0).is_err());
+    }
+
+    fn dca_vault() -> DcaVault {
+        DcaVault {
+            owner: Pubkey::default(),
+            token_mint: Pubkey::default(),
+            amount_per_period: 1_000,
+            interval_seconds: 3_600,
+            max_price: 500,
+            next_execution: 10_000,
+            balance: 1_100,
+            paused: false,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn dca_runs_when_due() {
+        assert_eq!(dca_vault().check_due(10_000, 500, 100).unwrap(), 1_100);
+    }
+
+    #[test]
+    fn dca_rejects_early_paused_expensive_or_empty_periods() {
+        let vault = dca_vault();
+        assert_eq!(vault.check_due(9_999, 500, 100).unwrap_err(), ErrorCode::DcaNotDue.into());
+        assert_eq!(vault.check_due(10_000, 501, 100).unwrap_err(), ErrorCode::DcaPriceAboveLimit.into());
+        assert_eq!(
+            vault.check_due(10_000, 500, 101).unwrap_err(),
+            ErrorCode::DcaInsufficientBalance.into()
+        );
+
+        let mut paused = dca_vault();
+        paused.paused = true;
+        assert_eq!(paused.check_due(10_000, 500, 100).unwrap_err(), ErrorCode::DcaPaused.into());
+    }
+
+    #[test]
+    fn launch_buy_over_cap_is_rejected() {
+        let mut state = user_state();
+        state.record_launch_buy(600, 1_000).unwrap();
+        assert_eq!(
+            state.record_launch_buy(401, 1_000).unwrap_err(),
+            ErrorCode::LaunchBuyLimitExceeded.into()
+        );
+    }
+
+    #[test]
+    fn buys_accumulate_within_a_slot() {
+        let mut state = user_state();
+        state.record_buy(7, 100, 0).unwrap();
+        state.record_buy(7, 50, 0).unwrap();
+        assert_eq!(state.bought_in_slot, 150);
+        assert!(state.bought_in(7));
+        assert!(!state.bought_in(8));
+    }
+
+    #[test]
+    fn new_slot_resets_the_cap() {
+        let mut state = user_state();
+        state.record_buy(7, 100, 100).unwrap();
+        state.record_buy(8, 100, 100).unwrap();
+        assert_eq!(state.bought_in_slot, 100);
+    }
+
+    #[test]
+    fn buy_over_slot_cap_is_rejected() {
+        let mut state = user_state();
+        state.record_buy(7, 60, 100).unwrap();
+        assert_eq!(
+            state.record_buy(7, 41, 100).unwrap_err(),
+            ErrorCode::SlotBuyLimitExceeded.into()
+        );
+    }
+}
@@ -0,0 +1,1348 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use crate::state::{
+    BondingCurveParams, CreatorProfile, LimitOrder, ModerationStatus, OrderSide, SameSlotSellPolicy,
+    TradeDeadline, TradeLeg, UserTradeState,
+};
+use crate::errors::ErrorCode;
+use crate::bonding_curve::BondingCurve;
+use crate::{CircuitBreakerTrippedEvent, ProgramConfig, TradeEvent, BATCH_LEG_ACCOUNTS, MAX_BATCH_LEGS};
+
+#[derive(Debug)]
+pub struct BuyQuote {
+    pub fee_amount: u64,
+    pub amount_after_fee: u64,
+    pub tokens_out: u64,
+}
+
+#[derive(Debug)]
+pub struct SellQuote {
+    pub sol_out: u64,
+    pub fee_amount: u64,
+    pub amount_after_fee: u64,
+}
+
+/// Prices a buy of `amount_in` lamports against the curve without moving funds.
+pub fn quote_buy(bonding_curve: &BondingCurveParams, amount_in: u64, fee_bps: u64) -> Result<BuyQuote> {
+    require!(amount_in > 0, ErrorCode::ZeroAmount);
+
+    // Get current market cap (total_liquidity)
+    let current_market_cap = bonding_curve.total_liquidity;
+
+    // Calculate fee
+    let fee_amount = calculate_fee(amount_in, fee_bps)?;
+    let amount_after_fee = amount_in
+        .checked_sub(fee_amount)
+        .ok_or(ErrorCode::CalculationError)?;
+
+    // Calculate tokens to receive using bonding curve
+    let tokens_out = BondingCurve::calculate_tokens_out(
+        amount_after_fee,
+        current_market_cap
+    )?;
+    require!(tokens_out > 0, ErrorCode::DustTrade);
+
+    Ok(BuyQuote { fee_amount, amount_after_fee, tokens_out })
+}
+
+/// Prices a sell of `amount_in` tokens against the curve without moving funds.
+pub fn quote_sell(bonding_curve: &BondingCurveParams, amount_in: u64, fee_bps: u64) -> Result<SellQuote> {
+    require!(amount_in > 0, ErrorCode::ZeroAmount);
+    require!(amount_in <= bonding_curve.current_supply, ErrorCode::SellExceedsSupply);
+
+    // Get current market cap (total_liquidity)
+    let current_market_cap = bonding_curve.total_liquidity;
+
+    // Calculate SOL to receive using bonding curve
+    let sol_out = BondingCurve::calculate_sol_out(
+        amount_in,
+        current_market_cap
+    )?;
+    require!(sol_out > 0, ErrorCode::DustTrade);
+    require!(sol_out <= bonding_curve.total_liquidity, ErrorCode::InsufficientLiquidity);
+
+    // Calculate fee
+    let fee_amount = calculate_fee(sol_out, fee_bps)?;
+    let amount_after_fee = sol_out
+        .checked_sub(fee_amount)
+        .ok_or(ErrorCode::CalculationError)?;
+
+    Ok(SellQuote { sol_out, fee_amount, amount_after_fee })
+}
+
+/// Snapshot of a curve's reserve and supply against what its state says they
+/// should be. Trades require both invariants to hold; `audit_curve` reports it.
+#[derive(Debug)]
+pub struct CurveAudit {
+    pub reserve: u64,
+    pub required_reserve: u64,
+    pub mint_supply: u64,
+    pub expected_supply: u64,
+}
+
+impl CurveAudit {
+    /// The reserve must cover every unit of liquidity. A SOL curve's PDA
+    /// holds it on top of its own rent-exempt minimum, so sells can never
+    /// drain it below rent; an SPL-quoted curve holds it in its quote vault.
+    pub fn is_solvent(&self) -> bool {
+        self.reserve >= self.required_reserve
+    }
+
+    /// Every token the curve issued is still in circulation. Reported by
+    /// `audit_curve` only: holders can burn their own tokens, so trades can't
+    /// depend on it.
+    pub fn supply_matches(&self) -> bool {
+        self.mint_supply == self.expected_supply
+    }
+
+    /// No tokens exist beyond what the curve issued.
+    pub fn supply_within_issued(&self) -> bool {
+        self.mint_supply <= self.expected_supply
+    }
+
+    pub fn require_healthy(&self) -> Result<()> {
+        require!(self.is_solvent(), ErrorCode::InsufficientLiquidity);
+        require!(self.supply_within_issued(), ErrorCode::CurveSupplyMismatch);
+        Ok(())
+    }
+}
+
+/// Audits a curve. `quote_vault` must be the curve's quote vault for an
+/// SPL-quoted curve and `None` for a SOL curve.
+pub fn audit_curve(
+    bonding_curve: &Account<BondingCurveParams>,
+    token_mint: &Mint,
+    quote_vault: Option<&TokenAccount>,
+) -> Result<CurveAudit> {
+    let (reserve, required_reserve) = match (bonding_curve.spl_quote, quote_vault) {
+        (true, Some(vault)) => (vault.amount, bonding_curve.total_liquidity),
+        (false, None) => {
+            let curve_info = bonding_curve.to_account_info();
+            let rent_exempt = Rent::get()?.minimum_balance(curve_info.data_len());
+            let required_lamports = bonding_curve
+                .total_liquidity
+                .checked_add(rent_exempt)
+                .ok_or(ErrorCode::Overflow)?;
+            (curve_info.lamports(), required_lamports)
+        }
+        _ => return err!(ErrorCode::QuoteCurrencyMismatch),
+    };
+
+    Ok(CurveAudit {
+        reserve,
+        required_reserve,
+        mint_supply: token_mint.supply,
+        expected_supply: bonding_curve.current_supply,
+    })
+}
+
+/// `share_bps` of a token balance, rounded down; 10_000 is the whole balance.
+pub fn share_of_balance(balance: u64, share_bps: u16) -> Result<u64> {
+    require!((1..=10_000).contains(&share_bps), ErrorCode::InvalidSellShare);
+    let amount = u128::from(balance) * u128::from(share_bps) / 10_000;
+    Ok(amount as u64)
+}
+
+/// Trading fee on `amount` at `fee_bps` basis points, rounded down.
+pub fn calculate_fee(amount: u64, fee_bps: u64) -> Result<u64> {
+    amount
+        .checked_mul(fee_bps)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_div(10000)
+        .ok_or(error!(ErrorCode::CalculationError))
+}
+
+/// Checks that the market takes trades right now: trading isn't paused, the
+/// curve isn't halted and any launch delay, presale or auction is over.
+pub fn require_market_open(
+    program_config: &ProgramConfig,
+    bonding_curve: &BondingCurveParams,
+    slot: u64,
+) -> Result<()> {
+    require!(!program_config.trading_paused, ErrorCode::TradingPaused);
+    require!(!bonding_curve.halted, ErrorCode::MarketHalted);
+    require!(bonding_curve.trading_open(slot), ErrorCode::TradingNotOpen);
+    Ok(())
+}
+
+/// Delisted tokens can only be sold. `Flagged` and `Hidden` are frontend
+/// signals and don't restrict trading.
+pub fn require_buys_allowed(bonding_curve: &BondingCurveParams) -> Result<()> {
+    require!(
+        bonding_curve.moderation_status != ModerationStatus::Delisted,
+        ErrorCode::TokenDelisted
+    );
+    Ok(())
+}
+
+/// Rejects a user trade executing after its deadline, if it set one, so a
+/// transaction stuck in a congested queue can't fill at a stale price.
+pub fn require_before_deadline(deadline: Option<TradeDeadline>, clock: &Clock) -> Result<()> {
+    let passed = deadline.is_some_and(|deadline| deadline.has_passed(clock.unix_timestamp, clock.slot));
+    require!(!passed, ErrorCode::TradeDeadlineExceeded);
+    Ok(())
+}
+
+/// Fee for a user's trade: the curve's launch fee decaying towards the normal
+/// fee, plus the same-slot surcharge on a sell right after the user's own buy
+/// (or a rejection, depending on the config), since that's how sandwiches close.
+pub fn user_fee_bps(
+    program_config: &ProgramConfig,
+    bonding_curve: &BondingCurveParams,
+    user_trade_state: &UserTradeState,
+    is_buy: bool,
+    clock: &Clock,
+) -> Result<u64> {
+    let fee_bps = bonding_curve.trading_fee_bps(
+        u64::from(program_config.trading_fee_bps),
+        clock.unix_timestamp,
+    );
+    if is_buy || !user_trade_state.bought_in(clock.slot) {
+        return Ok(fee_bps);
+    }
+
+    match program_config.same_slot_sell_policy {
+        SameSlotSellPolicy::Allow => Ok(fee_bps),
+        SameSlotSellPolicy::Reject => err!(ErrorCode::SameSlotSell),
+        SameSlotSellPolicy::Surcharge => Ok(fee_bps
+            .saturating_add(u64::from(program_config.same_slot_surcharge_bps))
+            .min(10_000)),
+    }
+}
+
+/// Counts a user's buy against the per-slot cap and, during the launch
+/// window, the per-wallet launch cap.
+pub fn record_user_buy(
+    program_config: &ProgramConfig,
+    bonding_curve: &BondingCurveParams,
+    user_trade_state: &mut UserTradeState,
+    amount_in: u64,
+    slot: u64,
+) -> Result<()> {
+    user_trade_state.record_buy(slot, amount_in, program_config.max_buy_per_slot)?;
+    if bonding_curve.in_launch_window(slot) {
+        user_trade_state.record_launch_buy(
+            amount_in,
+            bonding_curve.launch_protection.max_buy_per_wallet,
+        )?;
+    }
+    Ok(())
+}
+
+/// Previews a trade against the circuit breaker. If it would move the price
+/// past the threshold the market is halted until a moderator resets it and
+/// `true` is returned; the caller must then skip the trade but still succeed,
+/// otherwise the halt itself would be rolled back.
+///
+/// `balance_in` is what the trader holds of the input side. A trade they
+/// couldn't pay for is rejected instead, so halting a market costs at least
+/// the funds for the breaching trade.
+#[allow(clippy::too_many_arguments)]
+pub fn halt_if_breaker_trips(
+    token_mint: Pubkey,
+    bonding_curve: &mut BondingCurveParams,
+    program_config: &ProgramConfig,
+    is_buy: bool,
+    amount_in: u64,
+    balance_in: u64,
+    fee_bps: u64,
+    clock: &Clock,
+) -> Result<bool> {
+    let new_liquidity = if is_buy {
+        let quote = quote_buy(bonding_curve, amount_in, fee_bps)?;
+        bonding_curve.total_liquidity
+            .checked_add(quote.amount_after_fee)
+            .ok_or(ErrorCode::Overflow)?
+    } else {
+        let quote = quote_sell(bonding_curve, amount_in, fee_bps)?;
+        bonding_curve.total_liquidity
+            .checked_sub(quote.sol_out)
+            .ok_or(ErrorCode::CalculationError)?
+    };
+    if !circuit_breaker_tripped(bonding_curve, program_config, new_liquidity, clock.slot)? {
+        return Ok(false);
+    }
+    require!(balance_in >= amount_in, ErrorCode::InsufficientBalance);
+
+    bonding_curve.halted = true;
+    emit!(CircuitBreakerTrippedEvent {
+        token_mint,
+        reference_price: bonding_curve.reference_price,
+        attempted_price: BondingCurve::calculate_price(new_liquidity)?,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(true)
+}
+
+/// Checks that a resting order can fill right now and returns the spot price
+/// and fee to fill it at, or `None` when the fill tripped the circuit breaker
+/// and halted the market. A halted fill leaves the order and its escrow in
+/// place; anything else that stops the fill is an error.
+pub fn prepare_order_fill(
+    token_mint: Pubkey,
+    order: &LimitOrder,
+    bonding_curve: &mut BondingCurveParams,
+    program_config: &ProgramConfig,
+    clock: &Clock,
+) -> Result<Option<(u64, u64)>> {
+    require_market_open(program_config, bonding_curve, clock.slot)?;
+
+    let spot_price = BondingCurve::calculate_price(bonding_curve.total_liquidity)?;
+    require!(order.is_triggered(spot_price), ErrorCode::OrderNotTriggered);
+
+    let fee_bps = bonding_curve.trading_fee_bps(
+        u64::from(program_config.trading_fee_bps),
+        clock.unix_timestamp,
+    );
+    // The order's escrow already holds what it trades
+    if halt_if_breaker_trips(
+        token_mint,
+        bonding_curve,
+        program_config,
+        order.side == OrderSide::Buy,
+        order.amount,
+        order.amount,
+        fee_bps,
+        clock,
+    )? {
+        return Ok(None);
+    }
+
+    Ok(Some((spot_price, fee_bps)))
+}
+
+/// Moves the curve's reference price forward once the window has passed and
+/// reports whether a market at `new_liquidity` would be outside the breaker
+/// threshold. Always `false` when the breaker is disabled.
+pub fn circuit_breaker_tripped(
+    bonding_curve: &mut BondingCurveParams,
+    program_config: &ProgramConfig,
+    new_liquidity: u64,
+    slot: u64,
+) -> Result<bool> {
+    if program_config.circuit_breaker_bps == 0 {
+        return Ok(false);
+    }
+
+    let window_end = bonding_curve
+        .reference_slot
+        .saturating_add(program_config.circuit_breaker_window_slots);
+    if bonding_curve.reference_price == 0 || slot >= window_end {
+        bonding_curve.reference_price = BondingCurve::calculate_price(bonding_curve.total_liquidity)?;
+        bonding_curve.reference_slot = slot;
+    }
+
+    let reference_price = bonding_curve.reference_price as u128;
+    let new_price = BondingCurve::calculate_price(new_liquidity)? as u128;
+    let move_bps = new_price.abs_diff(reference_price) * 10_000 / reference_price.max(1);
+    Ok(move_bps > program_config.circuit_breaker_bps as u128)
+}
+
+/// The market side of a trade: the curve PDA, its mint, where fees go and
+/// the creator profile that tracks the market's stats.
+///
+/// Shared by `trade_token` and the creator dev-buy in `create_token` so both
+/// go through exactly the same curve math and fees.
+pub struct CurveTrade<'a, 'info> {
+    pub token_mint: &'a mut Account<'info, Mint>,
+    pub bonding_curve: &'a mut Account<'info, BondingCurveParams>,
+    pub creator_profile: &'a mut Account<'info, CreatorProfile>,
+    pub fee_collector: &'a AccountInfo<'info>,
+    pub token_program: &'a Program<'info, Token>,
+    pub system_program: &'a Program<'info, System>,
+}
+
+/// Token accounts a curve quoted in an SPL mint settles through, in place of
+/// the lamport transfers SOL curves use. For these trades `CurveTrade`'s
+/// `fee_collector` is the fee collector's token account for the quote mint.
+pub struct QuoteAccounts<'a, 'info> {
+    pub vault: &'a mut Account<'info, TokenAccount>,
+    pub trader_account: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> CurveTrade<'a, 'info> {
+    /// Re-reads the mint (and quote vault) after the token CPIs and checks
+    /// the curve invariants.
+    fn check_invariants(&mut self, quote_vault: Option<&mut Account<'info, TokenAccount>>) -> Result<()> {
+        self.token_mint.reload()?;
+        let quote_vault = match quote_vault {
+            Some(vault) => {
+                vault.reload()?;
+                Some(&**vault)
+            }
+            None => None,
+        };
+        audit_curve(self.bonding_curve, self.token_mint, quote_vault)?.require_healthy()
+    }
+
+    /// Prices a buy and checks the market takes it.
+    fn prepare_buy(&self, amount_in: u64, min_amount_out: u64, fee_bps: u64) -> Result<BuyQuote> {
+        require_buys_allowed(self.bonding_curve)?;
+
+        let quote = quote_buy(self.bonding_curve, amount_in, fee_bps)?;
+        require!(quote.tokens_out >= min_amount_out, ErrorCode::SlippageExceeded);
+
+        // Check if we should transition to Raydium
+        if BondingCurve::should_transition_to_raydium(self.bonding_curve.current_supply) {
+            return Err(ErrorCode::TransitionToRaydium.into());
+        }
+
+        Ok(quote)
+    }
+
+    /// Mints the bought tokens to `recipient` and books the buy, once the
+    /// payment has reached the reserve.
+    fn complete_buy(
+        &mut self,
+        buyer: Pubkey,
+        recipient: &AccountInfo<'info>,
+        amount_in: u64,
+        quote: &BuyQuote,
+        quote_vault: Option<&mut Account<'info, TokenAccount>>,
+    ) -> Result<u64> {
+        // Mint tokens to recipient, signed by the curve PDA
+        let token_mint_key = self.token_mint.key();
+        let seeds = &[
+            b"curve".as_ref(),
+            token_mint_key.as_ref(),
+            &[self.bonding_curve.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        let mint_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            token::MintTo {
+                mint: self.token_mint.to_account_info(),
+                to: recipient.clone(),
+                authority: self.bonding_curve.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::mint_to(mint_ctx, quote.tokens_out)?;
+
+        // Update bonding curve state
+        self.bonding_curve.current_supply = self.bonding_curve.current_supply
+            .checked_add(quote.tokens_out)
+            .ok_or(ErrorCode::Overflow)?;
+        self.bonding_curve.total_liquidity = self.bonding_curve.total_liquidity
+            .checked_add(quote.amount_after_fee)
+            .ok_or(ErrorCode::Overflow)?;
+
+        // Update creator stats, counting the buy that fills the curve as a graduation
+        self.creator_profile.record_trade(amount_in, quote.fee_amount);
+        if BondingCurve::should_transition_to_raydium(self.bonding_curve.current_supply) {
+            self.creator_profile.tokens_graduated =
+                self.creator_profile.tokens_graduated.saturating_add(1);
+        }
+
+        self.check_invariants(quote_vault)?;
+
+        emit!(TradeEvent {
+            user: buyer,
+            token_mint: token_mint_key,
+            amount_in,
+            amount_out: quote.tokens_out,
+            is_buy: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(quote.tokens_out)
+    }
+
+    /// Buys with `amount_in` lamports from `payer` and mints the tokens to
+    /// `recipient`. Returns the number of tokens minted.
+    pub fn buy(
+        &mut self,
+        payer: &AccountInfo<'info>,
+        recipient: &AccountInfo<'info>,
+        amount_in: u64,
+        min_amount_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(!self.bonding_curve.spl_quote, ErrorCode::QuoteCurrencyMismatch);
+        let quote = self.prepare_buy(amount_in, min_amount_out, fee_bps)?;
+
+        // Transfer SOL fee to fee collector
+        transfer_lamports(payer, self.fee_collector, self.system_program, quote.fee_amount)?;
+
+        // Transfer SOL from payer to bonding curve
+        let bonding_curve_info = self.bonding_curve.to_account_info();
+        transfer_lamports(payer, &bonding_curve_info, self.system_program, quote.amount_after_fee)?;
+
+        self.complete_buy(payer.key(), recipient, amount_in, &quote, None)
+    }
+
+    /// `buy` for a curve quoted in an SPL mint: `payer` pays `amount_in` of
+    /// the quote mint from `quote_accounts.trader_account`.
+    pub fn buy_with_quote(
+        &mut self,
+        quote_accounts: QuoteAccounts<'_, 'info>,
+        payer: &AccountInfo<'info>,
+        recipient: &AccountInfo<'info>,
+        amount_in: u64,
+        min_amount_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(self.bonding_curve.spl_quote, ErrorCode::QuoteCurrencyMismatch);
+        let quote = self.prepare_buy(amount_in, min_amount_out, fee_bps)?;
+
+        let vault_info = quote_accounts.vault.to_account_info();
+        transfer_tokens(
+            self.token_program,
+            quote_accounts.trader_account,
+            self.fee_collector,
+            payer,
+            &[],
+            quote.fee_amount,
+        )?;
+        transfer_tokens(
+            self.token_program,
+            quote_accounts.trader_account,
+            &vault_info,
+            payer,
+            &[],
+            quote.amount_after_fee,
+        )?;
+
+        self.complete_buy(payer.key(), recipient, amount_in, &quote, Some(quote_accounts.vault))
+    }
+
+    /// Prices a sell and burns the tokens from `holder_token_account`.
+    fn prepare_sell(
+        &self,
+        holder_token_account: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        authority_seeds: &[&[&[u8]]],
+        amount_in: u64,
+        min_amount_out: u64,
+        fee_bps: u64,
+    ) -> Result<SellQuote> {
+        let quote = quote_sell(self.bonding_curve, amount_in, fee_bps)?;
+        require!(quote.sol_out >= min_amount_out, ErrorCode::SlippageExceeded);
+
+        // Burn tokens from holder
+        let burn_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            token::Burn {
+                mint: self.token_mint.to_account_info(),
+                from: holder_token_account.clone(),
+                authority: authority.clone(),
+            },
+            authority_seeds,
+        );
+        token::burn(burn_ctx, amount_in)?;
+
+        Ok(quote)
+    }
+
+    /// Books a sell once the proceeds have left the reserve.
+    fn complete_sell(
+        &mut self,
+        seller: Pubkey,
+        amount_in: u64,
+        quote: &SellQuote,
+        quote_vault: Option<&mut Account<'info, TokenAccount>>,
+    ) -> Result<u64> {
+        // Update bonding curve state
+        self.bonding_curve.current_supply = self.bonding_curve.current_supply
+            .checked_sub(amount_in)
+            .ok_or(ErrorCode::SellExceedsSupply)?;
+        self.bonding_curve.total_liquidity = self.bonding_curve.total_liquidity
+            .checked_sub(quote.sol_out)
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+
+        // Update creator stats
+        self.creator_profile.record_trade(quote.sol_out, quote.fee_amount);
+
+        self.check_invariants(quote_vault)?;
+
+        emit!(TradeEvent {
+            user: seller,
+            token_mint: self.token_mint.key(),
+            amount_in,
+            amount_out: quote.amount_after_fee,
+            is_buy: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(quote.amount_after_fee)
+    }
+
+    /// Burns `amount_in` tokens from `holder_token_account` and pays the SOL
+    /// (minus fee) to `recipient`. `authority_seeds` is empty when the token
+    /// owner signs the transaction itself. Returns the lamports paid out.
+    #[allow(clippy::too_many_arguments)]
+    pub fn sell(
+        &mut self,
+        holder_token_account: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        authority_seeds: &[&[&[u8]]],
+        recipient: &AccountInfo<'info>,
+        amount_in: u64,
+        min_amount_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(!self.bonding_curve.spl_quote, ErrorCode::QuoteCurrencyMismatch);
+        let quote = self.prepare_sell(
+            holder_token_account,
+            authority,
+            authority_seeds,
+            amount_in,
+            min_amount_out,
+            fee_bps,
+        )?;
+
+        // Transfer SOL fee to fee collector and the rest to recipient
+        let bonding_curve_info = self.bonding_curve.to_account_info();
+        transfer_lamports(&bonding_curve_info, self.fee_collector, self.system_program, quote.fee_amount)?;
+        transfer_lamports(&bonding_curve_info, recipient, self.system_program, quote.amount_after_fee)?;
+
+        self.complete_sell(authority.key(), amount_in, &quote, None)
+    }
+
+    /// `sell` for a curve quoted in an SPL mint: the holder signs and the
+    /// proceeds go to `quote_accounts.trader_account` out of the quote vault.
+    pub fn sell_for_quote(
+        &mut self,
+        quote_accounts: QuoteAccounts<'_, 'info>,
+        holder_token_account: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        amount_in: u64,
+        min_amount_out: u64,
+        fee_bps: u64,
+    ) -> Result<u64> {
+        require!(self.bonding_curve.spl_quote, ErrorCode::QuoteCurrencyMismatch);
+        let quote = self.prepare_sell(holder_token_account, authority, &[], amount_in, min_amount_out, fee_bps)?;
+
+        // Pay out of the vault, signed by the curve PDA that owns it
+        let token_mint_key = self.token_mint.key();
+        let seeds = &[
+            b"curve".as_ref(),
+            token_mint_key.as_ref(),
+            &[self.bonding_curve.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        let vault_info = quote_accounts.vault.to_account_info();
+        let bonding_curve_info = self.bonding_curve.to_account_info();
+        transfer_tokens(
+            self.token_program,
+            &vault_info,
+            self.fee_collector,
+            &bonding_curve_info,
+            signer_seeds,
+            quote.fee_amount,
+        )?;
+        transfer_tokens(
+            self.token_program,
+            &vault_info,
+            quote_accounts.trader_account,
+            &bonding_curve_info,
+            signer_seeds,
+            quote.amount_after_fee,
+        )?;
+
+        self.complete_sell(authority.key(), amount_in, &quote, Some(quote_accounts.vault))
+    }
+}
+
+/// A batch takes 1 to `MAX_BATCH_LEGS` legs and exactly `BATCH_LEG_ACCOUNTS`
+/// remaining accounts per leg.
+pub fn require_valid_batch(legs: usize, accounts: usize) -> Result<()> {
+    require!((1..=MAX_BATCH_LEGS).contains(&legs), ErrorCode::InvalidBatch);
+    require!(accounts == legs * BATCH_LEG_ACCOUNTS, ErrorCode::InvalidBatchAccounts);
+    Ok(())
+}
+
+/// Runs one leg of a `batch_trade`. `accounts` are the leg's token mint,
+/// bonding curve, creator profile, user token account and user trade state;
+/// since they come in through `remaining_accounts` every constraint that
+/// `TradeContext` declares is checked here by hand, and the changed program
+/// accounts are written back before returning.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_batch_leg<'info>(
+    leg: &TradeLeg,
+    accounts: &'info [AccountInfo<'info>],
+    user: &AccountInfo<'info>,
+    program_config: &ProgramConfig,
+    fee_collector: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    system_program: &Program<'info, System>,
+    clock: &Clock,
+) -> Result<()> {
+    let [mint_info, curve_info, profile_info, token_account_info, user_state_info] = accounts else {
+        return err!(ErrorCode::InvalidBatchAccounts);
+    };
+    let mint_key = leg.token_mint;
+    require_keys_eq!(mint_info.key(), mint_key, ErrorCode::InvalidBatchAccounts);
+
+    let mut token_mint = Account::<Mint>::try_from(mint_info)?;
+    let mut bonding_curve = Account::<BondingCurveParams>::try_from(curve_info)?;
+    let curve_address = Pubkey::create_program_address(
+        &[b"curve", mint_key.as_ref(), &[bonding_curve.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| error!(ErrorCode::InvalidBatchAccounts))?;
+    require_keys_eq!(curve_info.key(), curve_address, ErrorCode::InvalidBatchAccounts);
+    require!(
+        token_mint.mint_authority == COption::Some(curve_address),
+        ErrorCode::InvalidBatchAccounts
+    );
+
+    let mut creator_profile = Account::<CreatorProfile>::try_from(profile_info)?;
+    let profile_address = Pubkey::create_program_address(
+        &[b"creator", bonding_curve.creator.as_ref(), &[creator_profile.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| error!(ErrorCode::InvalidBatchAccounts))?;
+    require_keys_eq!(profile_info.key(), profile_address, ErrorCode::InvalidBatchAccounts);
+
+    let user_token_account = Account::<TokenAccount>::try_from(token_account_info)?;
+    require!(
+        user_token_account.owner == user.key() && user_token_account.mint == mint_key,
+        ErrorCode::InvalidBatchAccounts
+    );
+
+    let mut user_trade_state =
+        load_or_create_user_trade_state(user_state_info, user, &mint_key, system_program)?;
+
+    require_market_open(program_config, &bonding_curve, clock.slot)?;
+    let fee_bps = user_fee_bps(program_config, &bonding_curve, &user_trade_state, leg.is_buy, clock)?;
+
+    let balance_in = if leg.is_buy { user.lamports() } else { user_token_account.amount };
+    if halt_if_breaker_trips(
+        mint_key,
+        &mut bonding_curve,
+        program_config,
+        leg.is_buy,
+        leg.amount_in,
+        balance_in,
+        fee_bps,
+        clock,
+    )? {
+        // Persist the halt and skip the leg; later legs still run
+        bonding_curve.exit(&crate::ID)?;
+        user_trade_state.exit(&crate::ID)?;
+        return Ok(());
+    }
+    if leg.is_buy {
+        record_user_buy(program_config, &bonding_curve, &mut user_trade_state, leg.amount_in, clock.slot)?;
+    }
+
+    let mut curve_trade = CurveTrade {
+        token_mint: &mut token_mint,
+        bonding_curve: &mut bonding_curve,
+        creator_profile: &mut creator_profile,
+        fee_collector,
+        token_program,
+        system_program,
+    };
+    if leg.is_buy {
+        curve_trade.buy(user, token_account_info, leg.amount_in, leg.min_amount_out, fee_bps)?;
+    } else {
+        curve_trade.sell(
+            token_account_info,
+            user,
+            &[],
+            user,
+            leg.amount_in,
+            leg.min_amount_out,
+            fee_bps,
+        )?;
+    }
+
+    bonding_curve.exit(&crate::ID)?;
+    creator_profile.exit(&crate::ID)?;
+    user_trade_state.exit(&crate::ID)?;
+    Ok(())
+}
+
+/// Loads the user's trade state for a market, creating it (paid by `user`)
+/// the first time, like `init_if_needed` does for `trade_token`.
+fn load_or_create_user_trade_state<'info>(
+    info: &'info AccountInfo<'info>,
+    user: &AccountInfo<'info>,
+    token_mint: &Pubkey,
+    system_program: &Program<'info, System>,
+) -> Result<Account<'info, UserTradeState>> {
+    let (address, bump) = Pubkey::find_program_address(
+        &[b"user_state", token_mint.as_ref(), user.key.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(info.key(), address, ErrorCode::InvalidBatchAccounts);
+
+    if info.data_is_empty() {
+        let seeds = &[
+            b"user_state".as_ref(),
+            token_mint.as_ref(),
+            user.key.as_ref(),
+            &[bump],
+        ];
+        create_pda_account(info, user, 8 + UserTradeState::SIZE, &[&seeds[..]], system_program)?;
+
+        let user_trade_state = UserTradeState {
+            user: user.key(),
+            token_mint: *token_mint,
+            last_buy_slot: 0,
+            bought_in_slot: 0,
+            launch_bought: 0,
+            bump,
+        };
+        user_trade_state.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    }
+
+    Account::try_from(info)
+}
+
+/// Creates a program-owned PDA paid for by `payer`. `create_account` fails if
+/// the address already holds lamports, which anyone can send it, so in that
+/// case the rent is topped up and the account allocated and assigned instead,
+/// as `init` does.
+pub fn create_pda_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[&[u8]]],
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let rent_exempt = Rent::get()?.minimum_balance(space);
+
+    if info.lamports() == 0 {
+        let create_ctx = CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            CreateAccount {
+                from: payer.clone(),
+                to: info.clone(),
+            },
+            signer_seeds,
+        );
+        return system_program::create_account(create_ctx, rent_exempt, space as u64, &crate::ID);
+    }
+
+    transfer_lamports(
+        payer,
+        info,
+        system_program,
+        rent_exempt.saturating_sub(info.lamports()),
+    )?;
+    let allocate_ctx = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        Allocate {
+            account_to_allocate: info.clone(),
+        },
+        signer_seeds,
+    );
+    system_program::allocate(allocate_ctx, space as u64)?;
+    let assign_ctx = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        Assign {
+            account_to_assign: info.clone(),
+        },
+        signer_seeds,
+    );
+    system_program::assign(assign_ctx, &crate::ID)
+}
+
+/// Closes a program-owned account that isn't loaded as an `Account` (so
+/// `close =` can't be used), sending its rent to `destination`.
+pub fn close_program_account<'info>(
+    account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    destination.add_lamports(account.lamports())?;
+    account.sub_lamports(account.lamports())?;
+    account.assign(&system_program::ID);
+    account.realloc(0, false)?;
+    Ok(())
+}
+
+/// Closes an empty token account, sending its rent to `destination`.
+/// `signer_seeds` are the owning PDA's (an order or vault escrow), or empty
+/// when the owner signs the transaction itself.
+pub fn close_token_account<'info>(
+    token_account: &Account<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let close_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::CloseAccount {
+            account: token_account.to_account_info(),
+            destination: destination.clone(),
+            authority: authority.clone(),
+        },
+        signer_seeds,
+    );
+    token::close_account(close_ctx)
+}
+
+/// Returns everything in an order's token escrow to `destination` and closes
+/// it. The whole balance is swept, not just what the order escrowed, so
+/// tokens anyone else sends to the escrow can't keep it from closing.
+pub fn sweep_and_close_token_account<'info>(
+    token_account: &mut Account<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    rent_destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    token_account.reload()?;
+    transfer_tokens(
+        token_program,
+        &token_account.to_account_info(),
+        destination,
+        authority,
+        signer_seeds,
+        token_account.amount,
+    )?;
+    close_token_account(token_account, rent_destination, authority, token_program, signer_seeds)
+}
+
+/// Moves `amount` tokens between token accounts, signed by `authority` (with
+/// `signer_seeds` when it's a PDA).
+fn transfer_tokens<'info>(
+    token_program: &Program<'info, Token>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::Transfer {
+            from: from.clone(),
+            to: to.clone(),
+            authority: authority.clone(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, amount)
+}
+
+/// Moves lamports between accounts. Accounts owned by this program (the curve
+/// PDA) can't be debited through the system program, so their balance is
+/// adjusted directly; anything else must be a signer and goes through
+/// `system_program::transfer`.
+pub fn transfer_lamports<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    if from.owner == &crate::ID {
+        from.sub_lamports(amount)?;
+        to.add_lamports(amount)?;
+    } else {
+        let transfer_ctx = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: from.clone(),
+                to: to.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(transfer_ctx, amount)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{LaunchProtection, CURRENT_ACCOUNT_VERSION, CURVE_RESERVED_BYTES};
+    use crate::CONFIG_RESERVED_BYTES;
+
+    fn curve(current_supply: u64, total_liquidity: u64) -> BondingCurveParams {
+        BondingCurveParams {
+            version: CURRENT_ACCOUNT_VERSION,
+            initial_price: 1_000_000,
+            slope: 100,
+            liquidity_target: 17_000_000_000,
+            current_supply,
+            total_liquidity,
+            bump: 255,
+            creator: Pubkey::default(),
+            moderation_status: ModerationStatus::Active,
+            moderation_reason: 0,
+            reference_price: 0,
+            reference_slot: 0,
+            halted: false,
+            launch_slot: 0,
+            launch_time: 0,
+            launch_protection: LaunchProtection::default(),
+            presale_end_slot: 0,
+            auction_active: false,
+            spl_quote: false,
+            reserved: [0; CURVE_RESERVED_BYTES],
+        }
+    }
+
+    fn config(circuit_breaker_bps: u16) -> ProgramConfig {
+        ProgramConfig {
+            version: CURRENT_ACCOUNT_VERSION,
+            fee_collector: Pubkey::default(),
+            admin: Pubkey::default(),
+            trading_paused: false,
+            trading_fee_bps: 100,
+            creation_fee_lamports: 0,
+            bump: 255,
+            pending_admin: None,
+            pending_admin_eta: 0,
+            pending_admin_expires_at: 0,
+            timelock_delay: 0,
+            next_change_id: 0,
+            pending_changes: Vec::new(),
+            circuit_breaker_bps,
+            circuit_breaker_window_slots: 150,
+            same_slot_sell_policy: SameSlotSellPolicy::Allow,
+            same_slot_surcharge_bps: 0,
+            max_buy_per_slot: 0,
+            reserved: [0; CONFIG_RESERVED_BYTES],
+        }
+    }
+
+    #[test]
+    fn breach_halts_the_market_until_reset() {
+        let config = config(1_000);
+        let mut market = curve(1_000_000_000, 1_000_000_000);
+        let mint = Pubkey::new_unique();
+        let clock = Clock { slot: 10, ..Clock::default() };
+        let big = 100_000_000_000;
+
+        let small = 1_000_000;
+
+        assert!(!halt_if_breaker_trips(mint, &mut market, &config, true, small, small, 100, &clock).unwrap());
+        assert!(halt_if_breaker_trips(mint, &mut market, &config, true, big, big, 100, &clock).unwrap());
+        assert!(market.halted);
+        assert_eq!(
+            require_market_open(&config, &market, clock.slot).unwrap_err(),
+            ErrorCode::MarketHalted.into()
+        );
+
+        let price = market.reset_circuit_breaker(20).unwrap();
+        assert!(!market.halted);
+        assert_eq!(market.reference_price, price);
+        assert_eq!(market.reference_slot, 20);
+        assert!(require_market_open(&config, &market, 20).is_ok());
+        let later = Clock { slot: 20, ..clock };
+        assert!(!halt_if_breaker_trips(mint, &mut market, &config, true, small, small, 100, &later).unwrap());
+    }
+
+    #[test]
+    fn unfunded_breach_is_rejected_without_halting() {
+        let config = config(1_000);
+        let mut market = curve(1_000_000_000, 1_000_000_000);
+        let clock = Clock { slot: 10, ..Clock::default() };
+        let mint = Pubkey::new_unique();
+        assert_eq!(
+            halt_if_breaker_trips(mint, &mut market, &config, true, 100_000_000_000, 1_000, 100, &clock)
+                .unwrap_err(),
+            ErrorCode::InsufficientBalance.into()
+        );
+        assert!(!market.halted);
+    }
+
+    #[test]
+    fn stop_fill_past_the_breaker_halts_and_keeps_the_order() {
+        let config = config(1_000);
+        let mut market = curve(1_000_000_000_000, 10_000_000);
+        let spot_price = BondingCurve::calculate_price(market.total_liquidity).unwrap();
+        let order = LimitOrder {
+            owner: Pubkey::default(),
+            token_mint: Pubkey::default(),
+            order_id: 0,
+            side: OrderSide::Sell,
+            amount: 100_000_000_000,
+            limit_price: 0,
+            stop_price: spot_price,
+            min_amount_out: 0,
+            created_at: 0,
+            bump: 255,
+        };
+        let clock = Clock { slot: 10, ..Clock::default() };
+        let mint = Pubkey::new_unique();
+
+        // The stop is hit, but dumping the whole order would break the band:
+        // the market halts and the fill is skipped, leaving the order open
+        assert!(order.is_triggered(spot_price));
+        assert!(prepare_order_fill(mint, &order, &mut market, &config, &clock).unwrap().is_none());
+        assert!(market.halted);
+
+        let small = LimitOrder { amount: 1_000_000_000, ..order };
+        assert_eq!(
+            prepare_order_fill(mint, &small, &mut market, &config, &clock).unwrap_err(),
+            ErrorCode::MarketHalted.into()
+        );
+        market.reset_circuit_breaker(clock.slot).unwrap();
+        assert!(prepare_order_fill(mint, &small, &mut market, &config, &clock).unwrap().is_some());
+    }
+
+    #[test]
+    fn delisted_tokens_can_only_be_sold() {
+        let mut market = curve(1_000_000_000, 1_000_000_000);
+        market.moderation_status = ModerationStatus::Delisted;
+        assert_eq!(require_buys_allowed(&market).unwrap_err(), ErrorCode::TokenDelisted.into());
+        assert!(require_market_open(&config(0), &market, 10).is_ok());
+        assert!(quote_sell(&market, 1_000_000, 100).is_ok());
+    }
+
+    #[test]
+    fn flagged_and_hidden_tokens_can_still_be_bought() {
+        for status in [ModerationStatus::Active, ModerationStatus::Flagged, ModerationStatus::Hidden] {
+            let mut market = curve(0, 0);
+            market.moderation_status = status;
+            assert!(require_buys_allowed(&market).is_ok());
+        }
+    }
+
+    /// Leaked so the accounts live for `'info`, as in a real instruction.
+    fn account_info(key: Pubkey, owner: Pubkey, executable: bool, data: Vec<u8>) -> AccountInfo<'static> {
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            true,
+            Box::leak(Box::new(1_000_000_000)),
+            Box::leak(data.into_boxed_slice()),
+            Box::leak(Box::new(owner)),
+            executable,
+            0,
+        )
+    }
+
+    fn mint_account(key: Pubkey, mint_authority: Pubkey) -> AccountInfo<'static> {
+        use anchor_lang::solana_program::program_pack::Pack;
+        let mint = token::spl_token::state::Mint {
+            mint_authority: COption::Some(mint_authority),
+            supply: 0,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; token::spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        account_info(key, token::ID, false, data)
+    }
+
+    fn curve_account(key: Pubkey, bump: u8) -> AccountInfo<'static> {
+        let mut data = Vec::new();
+        BondingCurveParams { bump, ..curve(0, 0) }.try_serialize(&mut data).unwrap();
+        account_info(key, crate::ID, false, data)
+    }
+
+    /// Runs a leg for `mint` against `accounts`, which must be rejected
+    /// before any funds move.
+    fn run_leg(mint: Pubkey, accounts: Vec<AccountInfo<'static>>) -> Error {
+        let leg = TradeLeg { token_mint: mint, is_buy: true, amount_in: 1_000_000, min_amount_out: 0 };
+        let user = account_info(Pubkey::new_unique(), system_program::ID, false, Vec::new());
+        let fee_collector = account_info(Pubkey::new_unique(), system_program::ID, false, Vec::new());
+        let token_program_info = Box::leak(Box::new(account_info(token::ID, Pubkey::default(), true, Vec::new())));
+        let system_program_info =
+            Box::leak(Box::new(account_info(system_program::ID, Pubkey::default(), true, Vec::new())));
+        execute_batch_leg(
+            &leg,
+            Box::leak(accounts.into_boxed_slice()),
+            &user,
+            &config(0),
+            &fee_collector,
+            &Program::try_from(&*token_program_info).unwrap(),
+            &Program::try_from(&*system_program_info).unwrap(),
+            &Clock::default(),
+        )
+        .unwrap_err()
+    }
+
+    /// The leg's accounts with the curve at its real PDA and the mint
+    /// controlled by it; each test breaks one of them.
+    fn leg_accounts(mint: Pubkey) -> Vec<AccountInfo<'static>> {
+        let (curve_address, bump) = Pubkey::find_program_address(&[b"curve", mint.as_ref()], &crate::ID);
+        vec![
+            mint_account(mint, curve_address),
+            curve_account(curve_address, bump),
+            account_info(Pubkey::new_unique(), crate::ID, false, Vec::new()),
+            account_info(Pubkey::new_unique(), token::ID, false, Vec::new()),
+            account_info(Pubkey::new_unique(), system_program::ID, false, Vec::new()),
+        ]
+    }
+
+    #[test]
+    fn batch_leg_and_account_counts_are_checked() {
+        assert!(require_valid_batch(1, BATCH_LEG_ACCOUNTS).is_ok());
+        assert!(require_valid_batch(MAX_BATCH_LEGS, MAX_BATCH_LEGS * BATCH_LEG_ACCOUNTS).is_ok());
+        assert_eq!(require_valid_batch(0, 0).unwrap_err(), ErrorCode::InvalidBatch.into());
+        assert_eq!(
+            require_valid_batch(MAX_BATCH_LEGS + 1, (MAX_BATCH_LEGS + 1) * BATCH_LEG_ACCOUNTS).unwrap_err(),
+            ErrorCode::InvalidBatch.into()
+        );
+        assert_eq!(
+            require_valid_batch(2, 2 * BATCH_LEG_ACCOUNTS - 1).unwrap_err(),
+            ErrorCode::InvalidBatchAccounts.into()
+        );
+    }
+
+    #[test]
+    fn batch_leg_with_missing_accounts_is_rejected() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = leg_accounts(mint);
+        accounts.pop();
+        assert_eq!(run_leg(mint, accounts), ErrorCode::InvalidBatchAccounts.into());
+    }
+
+    #[test]
+    fn batch_leg_with_wrong_mint_is_rejected() {
+        let mint = Pubkey::new_unique();
+        let accounts = leg_accounts(mint);
+        assert_eq!(run_leg(Pubkey::new_unique(), accounts), ErrorCode::InvalidBatchAccounts.into());
+    }
+
+    #[test]
+    fn batch_leg_with_wrong_curve_is_rejected() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = leg_accounts(mint);
+        let (_, bump) = Pubkey::find_program_address(&[b"curve", mint.as_ref()], &crate::ID);
+        accounts[1] = curve_account(Pubkey::new_unique(), bump);
+        assert_eq!(run_leg(mint, accounts), ErrorCode::InvalidBatchAccounts.into());
+    }
+
+    #[test]
+    fn batch_leg_with_foreign_mint_authority_is_rejected() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = leg_accounts(mint);
+        accounts[0] = mint_account(mint, Pubkey::new_unique());
+        assert_eq!(run_leg(mint, accounts), ErrorCode::InvalidBatchAccounts.into());
+    }
+
+    #[test]
+    fn fee_is_rounded_down() {
+        assert_eq!(calculate_fee(10_000, 100).unwrap(), 100);
+        assert_eq!(calculate_fee(99, 100).unwrap(), 0);
+    }
+
+    #[test]
+    fn fee_overflow_is_an_error() {
+        assert_eq!(calculate_fee(u64::MAX, 100).unwrap_err(), ErrorCode::Overflow.into());
+    }
+
+    #[test]
+    fn buy_quote_splits_fee() {
+        let quote = quote_buy(&curve(0, 0), 1_000_000_000, 100).unwrap();
+        assert_eq!(quote.fee_amount, 10_000_000);
+        assert_eq!(quote.amount_after_fee, 990_000_000);
+        assert!(quote.tokens_out > 0);
+    }
+
+    #[test]
+    fn zero_buy_is_rejected() {
+        assert_eq!(quote_buy(&curve(0, 0), 0, 100).unwrap_err(), ErrorCode::ZeroAmount.into());
+    }
+
+    #[test]
+    fn dust_buy_is_rejected() {
+        // A 100% fee leaves nothing to buy with
+        assert_eq!(quote_buy(&curve(0, 0), 1, 10_000).unwrap_err(), ErrorCode::DustTrade.into());
+    }
+
+    #[test]
+    fn buy_overflow_is_an_error() {
+        assert_eq!(quote_buy(&curve(0, 0), u64::MAX, 0).unwrap_err(), ErrorCode::Overflow.into());
+    }
+
+    #[test]
+    fn sell_quote_splits_fee() {
+        let quote = quote_sell(&curve(1_000_000_000_000, 1_000_000_000), 1_000_000_000, 100).unwrap();
+        assert_eq!(quote.fee_amount, quote.sol_out / 100);
+        assert_eq!(quote.amount_after_fee, quote.sol_out - quote.fee_amount);
+    }
+
+    #[test]
+    fn zero_sell_is_rejected() {
+        assert_eq!(quote_sell(&curve(1_000, 0), 0, 100).unwrap_err(), ErrorCode::ZeroAmount.into());
+    }
+
+    #[test]
+    fn sell_beyond_supply_is_rejected() {
+        assert_eq!(
+            quote_sell(&curve(1_000, 0), 1_001, 100).unwrap_err(),
+            ErrorCode::SellExceedsSupply.into()
+        );
+    }
+
+    #[test]
+    fn sell_beyond_liquidity_is_rejected() {
+        assert_eq!(
+            quote_sell(&curve(1_000_000_000_000, 1), 1_000_000_000_000, 100).unwrap_err(),
+            ErrorCode::InsufficientLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn share_of_balance_rounds_down() {
+        assert_eq!(share_of_balance(1_000_001, 10_000).unwrap(), 1_000_001);
+        assert_eq!(share_of_balance(1_000_001, 5_000).unwrap(), 500_000);
+        assert_eq!(share_of_balance(u64::MAX, 10_000).unwrap(), u64::MAX);
+        assert_eq!(share_of_balance(9_999, 1).unwrap(), 0);
+    }
+
+    #[test]
+    fn share_outside_range_is_rejected() {
+        for share_bps in [0, 10_001] {
+            assert_eq!(
+                share_of_balance(1_000, share_bps).unwrap_err(),
+                ErrorCode::InvalidSellShare.into()
+            );
+        }
+    }
+
+    #[test]
+    fn audit_flags_insolvent_curve() {
+        let audit = CurveAudit {
+            reserve: 999,
+            required_reserve: 1_000,
+            mint_supply: 5,
+            expected_supply: 5,
+        };
+        assert!(!audit.is_solvent());
+        assert_eq!(audit.require_healthy().unwrap_err(), ErrorCode::InsufficientLiquidity.into());
+    }
+
+    #[test]
+    fn audit_flags_supply_mismatch() {
+        let audit = CurveAudit {
+            reserve: 1_000,
+            required_reserve: 1_000,
+            mint_supply: 6,
+            expected_supply: 5,
+        };
+        assert!(audit.is_solvent());
+        assert_eq!(audit.require_healthy().unwrap_err(), ErrorCode::CurveSupplyMismatch.into());
+    }
+
+    #[test]
+    fn holder_burns_dont_block_trading() {
+        let audit = CurveAudit {
+            reserve: 1_000,
+            required_reserve: 1_000,
+            mint_supply: 4,
+            expected_supply: 5,
+        };
+        assert!(!audit.supply_matches());
+        assert!(audit.require_healthy().is_ok());
+    }
+
+    #[test]
+    fn dust_sell_is_rejected() {
+        assert_eq!(quote_sell(&curve(1_000, 0), 1, 100).unwrap_err(), ErrorCode::DustTrade.into());
+    }
+}
@@ -1,7 +1,13 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { PumpFun } from "../target/types/pump_fun";
-import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  transfer,
+} from "@solana/spl-token";
 import { SystemProgram } from "@solana/web3.js";
 import { assert } from "chai";
 
@@ -16,7 +22,9 @@ describe("PumpFun Token Program", () => {
   let tokenMetadata: anchor.web3.PublicKey;
   let bondingCurve: anchor.web3.PublicKey;
   let programConfig: anchor.web3.PublicKey;
+  let creatorProfile: anchor.web3.PublicKey;
   let feeCollector: anchor.web3.Keypair;
+  let feeVault: anchor.web3.PublicKey;
 
   // Test data
   const TOKEN_NAME = "Test Token";
@@ -30,20 +38,46 @@ describe("PumpFun Token Program", () => {
     feeCollector = anchor.web3.Keypair.generate();
     
     // Initialize program config
-    const programConfigKeypair = anchor.web3.Keypair.generate();
-    programConfig = programConfigKeypair.publicKey;
+    [programConfig] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [feeVault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_vault")],
+      program.programId
+    );
+    [creatorProfile] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("creator"), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+    // Only the upgrade authority (the local wallet) may initialize the config
+    const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
     
     await program.methods
       .initializeProgramConfig(
         feeCollector.publicKey,
-        provider.wallet.publicKey
+        provider.wallet.publicKey,
+        new anchor.BN(0) // no timelock delay so queued changes apply at once
       )
       .accounts({
         authority: provider.wallet.publicKey,
         programConfig: programConfig,
+        program: program.programId,
+        programData: programData,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .initializeFeeVault()
+      .accounts({
+        payer: provider.wallet.publicKey,
+        feeVault: feeVault,
         systemProgram: SystemProgram.programId,
       })
-      .signers([programConfigKeypair])
       .rpc();
   });
 
@@ -69,7 +103,9 @@ describe("PumpFun Token Program", () => {
         TOKEN_SYMBOL,
         TOKEN_DESCRIPTION,
         TOKEN_IMAGE,
-        INITIAL_SUPPLY
+        INITIAL_SUPPLY,
+        null,
+        null
       )
       .accounts({
         authority: provider.wallet.publicKey,
@@ -77,7 +113,11 @@ describe("PumpFun Token Program", () => {
         tokenMint: tokenMint,
         tokenMetadata: tokenMetadata,
         bondingCurve: bondingCurve,
+        creatorTokenAccount: null,
+        creatorProfile: creatorProfile,
+        feeVault: feeVault,
         tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
@@ -91,5 +131,273 @@ describe("PumpFun Token Program", () => {
     assert.equal(metadata.description, TOKEN_DESCRIPTION);
     assert.equal(metadata.imageUrl, TOKEN_IMAGE);
     assert.ok(metadata.creator.equals(provider.wallet.publicKey));
+
+    const profile = await program.account.creatorProfile.fetch(creatorProfile);
+    assert.ok(profile.creator.equals(provider.wallet.publicKey));
+    assert.equal(profile.tokensLaunched.toNumber(), 1);
+  });
+
+  it("Charges the creation fee into the fee vault", async () => {
+    const CREATION_FEE = new anchor.BN(10_000_000); // 0.01 SOL
+
+    await program.methods
+      .updateCreationFee(CREATION_FEE)
+      .accounts({
+        authority: provider.wallet.publicKey,
+        programConfig: programConfig,
+      })
+      .rpc();
+
+    // Fee changes are timelocked; execute the queued change
+    const config = await program.account.programConfig.fetch(programConfig);
+    await program.methods
+      .executeConfigChange(config.pendingChanges[0].id)
+      .accounts({ programConfig: programConfig })
+      .rpc();
+
+    const mintKeypair = anchor.web3.Keypair.generate();
+    const mint = mintKeypair.publicKey;
+    const balanceBefore = await provider.connection.getBalance(feeVault);
+
+    await program.methods
+      .createToken(
+        TOKEN_NAME,
+        TOKEN_SYMBOL,
+        TOKEN_DESCRIPTION,
+        TOKEN_IMAGE,
+        INITIAL_SUPPLY,
+        null,
+        null
+      )
+      .accounts({
+        authority: provider.wallet.publicKey,
+        programConfig: programConfig,
+        tokenMint: mint,
+        tokenMetadata: anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("metadata"), mint.toBuffer()],
+          program.programId
+        )[0],
+        bondingCurve: anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("curve"), mint.toBuffer()],
+          program.programId
+        )[0],
+        creatorTokenAccount: null,
+        creatorProfile: creatorProfile,
+        feeVault: feeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([mintKeypair])
+      .rpc();
+
+    const balanceAfter = await provider.connection.getBalance(feeVault);
+    assert.equal(balanceAfter - balanceBefore, CREATION_FEE.toNumber());
+  });
+
+  it("Only lets treasury withdrawers take fees out of the vault", async () => {
+    const amount = new anchor.BN(1_000_000);
+    const outsider = anchor.web3.Keypair.generate();
+
+    try {
+      await program.methods
+        .withdrawFees(amount)
+        .accounts({
+          authority: outsider.publicKey,
+          programConfig: programConfig,
+          accessControl: null,
+          feeVault: feeVault,
+        })
+        .signers([outsider])
+        .rpc();
+      assert.fail("withdrawal without the role should fail");
+    } catch (err) {
+      assert.include(err.toString(), "Unauthorized");
+    }
+
+    // The admin holds every role
+    const vaultBefore = await provider.connection.getBalance(feeVault);
+    await program.methods
+      .withdrawFees(amount)
+      .accounts({
+        authority: provider.wallet.publicKey,
+        programConfig: programConfig,
+        accessControl: null,
+        feeVault: feeVault,
+      })
+      .rpc();
+    const vaultAfter = await provider.connection.getBalance(feeVault);
+    assert.equal(vaultBefore - vaultAfter, amount.toNumber());
+  });
+
+  // Launches a token with a dev-buy so the wallet holds some to sell
+  async function launchWithDevBuy(): Promise<anchor.web3.PublicKey> {
+    const mintKeypair = anchor.web3.Keypair.generate();
+    const mint = mintKeypair.publicKey;
+
+    await program.methods
+      .createToken(
+        TOKEN_NAME,
+        TOKEN_SYMBOL,
+        TOKEN_DESCRIPTION,
+        TOKEN_IMAGE,
+        INITIAL_SUPPLY,
+        new anchor.BN(100_000_000), // 0.1 SOL dev-buy
+        null
+      )
+      .accounts({
+        authority: provider.wallet.publicKey,
+        programConfig: programConfig,
+        tokenMint: mint,
+        tokenMetadata: anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("metadata"), mint.toBuffer()],
+          program.programId
+        )[0],
+        bondingCurve: anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("curve"), mint.toBuffer()],
+          program.programId
+        )[0],
+        creatorTokenAccount: getAssociatedTokenAddressSync(
+          mint,
+          provider.wallet.publicKey
+        ),
+        creatorProfile: creatorProfile,
+        feeVault: feeVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([mintKeypair])
+      .rpc();
+
+    return mint;
+  }
+
+  // Places a sell order for `amount` tokens and sends one more token unit
+  // straight into its escrow, as a griefer could
+  async function placeGriefedSellOrder(
+    mint: anchor.web3.PublicKey,
+    amount: anchor.BN,
+    limitPrice: anchor.BN
+  ) {
+    const owner = provider.wallet.publicKey;
+    const orderId = new anchor.BN(0);
+    const [order] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("order"),
+        mint.toBuffer(),
+        owner.toBuffer(),
+        orderId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const orderTokenAccount = getAssociatedTokenAddressSync(mint, order, true);
+    const ownerTokenAccount = getAssociatedTokenAddressSync(mint, owner);
+
+    await program.methods
+      .placeOrder(orderId, { sell: {} }, amount, limitPrice, new anchor.BN(0), new anchor.BN(0))
+      .accounts({
+        owner: owner,
+        tokenMint: mint,
+        bondingCurve: anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("curve"), mint.toBuffer()],
+          program.programId
+        )[0],
+        order: order,
+        orderTokenAccount: orderTokenAccount,
+        ownerTokenAccount: ownerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await transfer(
+      provider.connection,
+      (provider.wallet as anchor.Wallet).payer,
+      ownerTokenAccount,
+      orderTokenAccount,
+      owner,
+      1
+    );
+
+    return { order, orderTokenAccount, ownerTokenAccount };
+  }
+
+  it("Cancels an order whose escrow holds extra tokens", async () => {
+    const mint = await launchWithDevBuy();
+    const ownerTokenAccount = getAssociatedTokenAddressSync(
+      mint,
+      provider.wallet.publicKey
+    );
+    const held = (await getAccount(provider.connection, ownerTokenAccount)).amount;
+
+    const { order, orderTokenAccount } = await placeGriefedSellOrder(
+      mint,
+      new anchor.BN(1_000_000),
+      new anchor.BN("18446744073709551615") // never triggers
+    );
+
+    await program.methods
+      .cancelOrder()
+      .accounts({
+        owner: provider.wallet.publicKey,
+        tokenMint: mint,
+        order: order,
+        orderTokenAccount: orderTokenAccount,
+        ownerTokenAccount: ownerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    // The escrowed tokens and the stray unit both come back
+    const after = (await getAccount(provider.connection, ownerTokenAccount)).amount;
+    assert.equal(after, held);
+    assert.isNull(await provider.connection.getAccountInfo(orderTokenAccount));
+    assert.isNull(await provider.connection.getAccountInfo(order));
+  });
+
+  it("Fills an order whose escrow holds extra tokens", async () => {
+    const mint = await launchWithDevBuy();
+    const owner = provider.wallet.publicKey;
+    const bondingCurve = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("curve"), mint.toBuffer()],
+      program.programId
+    )[0];
+    const amount = new anchor.BN(1_000_000);
+
+    const { order, orderTokenAccount, ownerTokenAccount } =
+      await placeGriefedSellOrder(mint, amount, new anchor.BN(1)); // triggers at once
+    const held = (await getAccount(provider.connection, ownerTokenAccount)).amount;
+
+    await program.methods
+      .fillOrder()
+      .accounts({
+        keeper: owner,
+        owner: owner,
+        order: order,
+        orderTokenAccount: orderTokenAccount,
+        ownerTokenAccount: ownerTokenAccount,
+        tokenMint: mint,
+        bondingCurve: bondingCurve,
+        creatorProfile: creatorProfile,
+        ownerTradeState: anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("user_state"), mint.toBuffer(), owner.toBuffer()],
+          program.programId
+        )[0],
+        feeVault: feeVault,
+        programConfig: programConfig,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // Only the order amount was sold; the stray unit is swept back
+    const after = (await getAccount(provider.connection, ownerTokenAccount)).amount;
+    assert.equal(after, held + BigInt(1));
+    assert.isNull(await provider.connection.getAccountInfo(orderTokenAccount));
+    assert.isNull(await provider.connection.getAccountInfo(order));
   });
 });